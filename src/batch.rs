@@ -0,0 +1,804 @@
+//! Settlement of many receipts to the same payee in one proof.
+//!
+//! [`ReceiptBatchCircuit`] verifies `N` distinct receipt signatures by the
+//! payee's `b_public_key` and exposes the total amount along with a
+//! commitment to the list of receipts (see [`commitment`]) and the nullifier
+//! of every receipt, so individual receipts stay private while the settled
+//! batch can later be opened against the commitment, and no receipt is
+//! settled twice whether in a batch or alone.
+
+use ff::PrimeFieldBits;
+use halo2::{
+    arithmetic::{CurveAffine, FieldExt},
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+use maingate::{AssignedValue, MainGate, MainGateInstructions, RegionCtx};
+
+use crate::{
+    comparison::LessThanChip,
+    ecdsa::{Ecdsa, Signature},
+    merkle::MerkleChip,
+    poseidon::{self, Domain, P128Pow5T3, PoseidonChip, Spec, P128_RATE, P128_WIDTH},
+    signature::ReceiptSignatureScheme,
+    Receipt, ReceiptConfig, DEFAULT_AMOUNT_NUM_BITS, DEFAULT_TIMESTAMP_NUM_BITS,
+    DEFAULT_WINDOW_SIZE,
+};
+
+/// Commitment to a batch of receipts, chaining their hashes in order: starting
+/// from `N`, each link is `Poseidon(Domain::ReceiptBatch, link, hash)`.
+pub fn commitment<F: FieldExt, const N: usize>(receipts: &[Receipt<F>; N]) -> F
+where
+    P128Pow5T3: Spec<F, P128_WIDTH, P128_RATE>,
+{
+    receipts.iter().fold(F::from(N as u64), |link, receipt| {
+        poseidon::hash::<F, P128Pow5T3, P128_WIDTH, P128_RATE, 3>([
+            Domain::ReceiptBatch.tag(),
+            link,
+            receipt.hash(),
+        ])
+    })
+}
+
+/// Proves that `signatures[i]` by `b_public_key` is a valid signature over
+/// receipt `{a_indices[i], b_index, amounts[i], expires_by[i]}` for every
+/// `i`, so that all `N` receipts are payable to the same account.
+///
+/// The signature scheme is `S`, e.g. [`Ecdsa`] over secp256k1 or
+/// [`crate::schnorr::Schnorr`] over Pallas, and `scheme` holds its layout
+/// parameters.
+///
+/// Receipt hashes are constrained to be pairwise distinct, so the same
+/// signed receipt can't fill several slots and count twice towards the
+/// total. Every receipt must not have expired at time `now`, and its
+/// nullifier is exposed as in [`crate::ReceiptCircuit`], keyed by the
+/// `nullifier_secret` committed to in the registry leaf of `b_index` at
+/// `b_registry_path`. Nullifiers are the same as those of the receipts
+/// redeemed alone, so one spent-set covers both circuits.
+///
+/// Every amount and their total are constrained to `amount_num_bits` bits,
+/// which also keeps the total from wrapping around the field modulus, and
+/// `now` and every `expires_by` to `timestamp_num_bits` bits.
+///
+/// Batches must not be empty, synthesis fails if `N` is zero.
+///
+/// Public inputs are described by [`ReceiptBatchPublicInputs`].
+#[derive(Clone, Debug)]
+pub struct ReceiptBatchCircuit<S: ReceiptSignatureScheme<F>, F: FieldExt, const N: usize> {
+    pub b_index: Value<F>,
+    pub b_public_key: Value<S::PublicKey>,
+    pub b_registry_path: Vec<Value<F>>,
+
+    pub a_indices: [Value<F>; N],
+    pub amounts: [Value<F>; N],
+    pub expires_by: [Value<F>; N],
+    pub signatures: [Value<S::Signature>; N],
+
+    pub now: Value<F>,
+    pub nullifier_secret: Value<F>,
+
+    pub timestamp_num_bits: usize,
+    pub amount_num_bits: usize,
+
+    pub scheme: S,
+}
+
+impl<E: CurveAffine, F: FieldExt, const N: usize> ReceiptBatchCircuit<Ecdsa<E>, F, N> {
+    /// Circuit proving that the ECDSA `signatures` by `b_public_key` are
+    /// valid for `receipts`, none of which has expired at time `now`, and
+    /// that `b_public_key` is registered with `nullifier_secret` at
+    /// `b_registry_path`.
+    ///
+    /// Timestamp and amount bit widths and window size are set to their
+    /// defaults and can be overridden on the returned circuit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is zero or the receipts aren't payable to the same
+    /// `b_index`.
+    pub fn from_receipts(
+        b_public_key: E,
+        receipts: &[Receipt<F>; N],
+        signatures: &[Signature<E>; N],
+        now: F,
+        nullifier_secret: F,
+        b_registry_path: &[F],
+    ) -> Self {
+        Self::from_receipts_with(
+            Ecdsa::new(DEFAULT_WINDOW_SIZE),
+            b_public_key,
+            receipts,
+            signatures,
+            now,
+            nullifier_secret,
+            b_registry_path,
+        )
+    }
+}
+
+impl<S: ReceiptSignatureScheme<F>, F: FieldExt, const N: usize> ReceiptBatchCircuit<S, F, N> {
+    /// Like [`ReceiptBatchCircuit::from_receipts`], for signatures of scheme
+    /// `S` laid out by `scheme`.
+    pub fn from_receipts_with(
+        scheme: S,
+        b_public_key: S::PublicKey,
+        receipts: &[Receipt<F>; N],
+        signatures: &[S::Signature; N],
+        now: F,
+        nullifier_secret: F,
+        b_registry_path: &[F],
+    ) -> Self {
+        assert!(N > 0, "empty batch");
+        let b_index = receipts[0].b_index;
+        assert!(
+            receipts.iter().all(|receipt| receipt.b_index == b_index),
+            "receipts of different payees"
+        );
+
+        Self {
+            b_index: Value::known(b_index),
+            b_public_key: Value::known(b_public_key),
+            b_registry_path: b_registry_path.iter().copied().map(Value::known).collect(),
+            a_indices: receipts.map(|receipt| Value::known(receipt.a_index)),
+            amounts: receipts.map(|receipt| Value::known(receipt.amount)),
+            expires_by: receipts.map(|receipt| Value::known(receipt.expires_by)),
+            signatures: signatures.map(Value::known),
+            now: Value::known(now),
+            nullifier_secret: Value::known(nullifier_secret),
+            timestamp_num_bits: DEFAULT_TIMESTAMP_NUM_BITS,
+            amount_num_bits: DEFAULT_AMOUNT_NUM_BITS,
+            scheme,
+        }
+    }
+
+    /// Parameters that change the layout of the circuit, and hence its keys,
+    /// without changing its constraint system.
+    pub fn shape_parameters(&self) -> Vec<u64> {
+        let mut parameters = vec![
+            N as u64,
+            self.timestamp_num_bits as u64,
+            self.amount_num_bits as u64,
+            self.b_registry_path.len() as u64,
+        ];
+        parameters.extend(self.scheme.shape_parameters());
+        parameters
+    }
+}
+
+/// Cells of the batch region used by the rest of the circuit.
+struct AssignedBatch<F: FieldExt> {
+    b_public_key: Vec<AssignedValue<F>>,
+    b_index: AssignedValue<F>,
+    amounts: Vec<AssignedValue<F>>,
+    expires_by: Vec<AssignedValue<F>>,
+    total: AssignedValue<F>,
+    commitment: AssignedValue<F>,
+    now: AssignedValue<F>,
+    registry_root: AssignedValue<F>,
+    nullifiers: Vec<AssignedValue<F>>,
+}
+
+impl<S: ReceiptSignatureScheme<F>, F: FieldExt + PrimeFieldBits, const N: usize> Circuit<F>
+    for ReceiptBatchCircuit<S, F, N>
+where
+    P128Pow5T3: Spec<F, P128_WIDTH, P128_RATE>,
+{
+    type Config = ReceiptConfig<F, S>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            b_index: Value::unknown(),
+            b_public_key: Value::unknown(),
+            b_registry_path: vec![Value::unknown(); self.b_registry_path.len()],
+            a_indices: [(); N].map(|_| Value::unknown()),
+            amounts: [(); N].map(|_| Value::unknown()),
+            expires_by: [(); N].map(|_| Value::unknown()),
+            signatures: [(); N].map(|_| Value::unknown()),
+            now: Value::unknown(),
+            nullifier_secret: Value::unknown(),
+            timestamp_num_bits: self.timestamp_num_bits,
+            amount_num_bits: self.amount_num_bits,
+            scheme: self.scheme,
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        ReceiptConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        if N == 0 {
+            return Err(Error::Synthesis);
+        }
+
+        let main_gate = MainGate::<F>::new(config.main_gate_config.clone());
+        let signature_chip = self
+            .scheme
+            .load_chip(&config.signature_config, &mut layouter)?;
+        let poseidon_chip = PoseidonChip::<F, P128Pow5T3, P128_WIDTH, P128_RATE>::new(
+            config.main_gate_config.clone(),
+        );
+        let merkle_chip = MerkleChip::new(config.main_gate_config.clone());
+
+        let batch = layouter.assign_region(
+            || "verify receipts",
+            |mut region| {
+                let offset = &mut 0;
+                let ctx = &mut RegionCtx::new(&mut region, offset);
+
+                // every receipt hashes the same payee cells
+                let b_index = main_gate.assign_value(ctx, self.b_index)?;
+                let b_public_key = S::assign_public_key(&signature_chip, ctx, self.b_public_key)?;
+                let now = main_gate.assign_value(ctx, self.now)?;
+                let nullifier_secret = main_gate.assign_value(ctx, self.nullifier_secret)?;
+
+                let mut amounts = vec![];
+                let mut expires_by = vec![];
+                let mut hashes = vec![];
+                let mut nullifiers = vec![];
+                for (((a_index, amount), receipt_expires_by), signature) in self
+                    .a_indices
+                    .iter()
+                    .zip(self.amounts.iter())
+                    .zip(self.expires_by.iter())
+                    .zip(self.signatures.iter())
+                {
+                    let a_index = main_gate.assign_value(ctx, *a_index)?;
+                    let amount = main_gate.assign_value(ctx, *amount)?;
+                    let receipt_expires_by = main_gate.assign_value(ctx, *receipt_expires_by)?;
+
+                    let receipt_tag = poseidon_chip.domain_tag(ctx, Domain::Receipt)?;
+                    let hash = poseidon_chip.hash(
+                        ctx,
+                        &[
+                            receipt_tag,
+                            a_index,
+                            b_index.clone(),
+                            amount.clone(),
+                            receipt_expires_by.clone(),
+                        ],
+                    )?;
+                    let signature = S::assign_signature(&signature_chip, ctx, *signature)?;
+                    S::verify(&signature_chip, ctx, &b_public_key, &hash, &signature)?;
+
+                    let nullifier_tag = poseidon_chip.domain_tag(ctx, Domain::Nullifier)?;
+                    let nullifier = poseidon_chip.hash(
+                        ctx,
+                        &[nullifier_tag, nullifier_secret.clone(), hash.clone()],
+                    )?;
+
+                    amounts.push(amount);
+                    expires_by.push(receipt_expires_by);
+                    hashes.push(hash);
+                    nullifiers.push(nullifier);
+                }
+
+                // a receipt filling several slots would count more than once
+                for (i, hash) in hashes.iter().enumerate() {
+                    for other in hashes[i + 1..].iter() {
+                        let diff = main_gate.sub(ctx, hash, other)?;
+                        main_gate.assert_not_zero(ctx, &diff)?;
+                    }
+                }
+
+                let total = amounts
+                    .iter()
+                    .skip(1)
+                    .try_fold(amounts[0].clone(), |total, amount| {
+                        main_gate.add(ctx, &total, amount)
+                    })?;
+                let mut commitment = main_gate.assign_constant(ctx, F::from(N as u64))?;
+                for hash in hashes {
+                    let batch_tag = poseidon_chip.domain_tag(ctx, Domain::ReceiptBatch)?;
+                    commitment = poseidon_chip.hash(ctx, &[batch_tag, commitment, hash])?;
+                }
+
+                let b_public_key = S::public_key_cells(&b_public_key);
+                let secret_commitment = merkle_chip.secret_commitment(ctx, &nullifier_secret)?;
+                let leaf =
+                    merkle_chip.registry_leaf(ctx, &b_index, &b_public_key, &secret_commitment)?;
+                let registry_root =
+                    merkle_chip.compute_root(ctx, &leaf, &b_index, &self.b_registry_path)?;
+
+                Ok(AssignedBatch {
+                    b_public_key,
+                    b_index,
+                    amounts,
+                    expires_by,
+                    total,
+                    commitment,
+                    now,
+                    registry_root,
+                    nullifiers,
+                })
+            },
+        )?;
+
+        let less_than_chip =
+            LessThanChip::new(config.main_gate_config.clone(), config.running_sum_config);
+        for (i, expires_by) in batch.expires_by.iter().enumerate() {
+            less_than_chip.assert_less_than(
+                layouter.namespace(|| format!("now < expires_by {}", i)),
+                &batch.now,
+                expires_by,
+                self.timestamp_num_bits,
+            )?;
+        }
+        for (i, amount) in batch.amounts.iter().enumerate() {
+            config.running_sum_config.range_check(
+                layouter.namespace(|| format!("amount {}", i)),
+                amount,
+                self.amount_num_bits,
+            )?;
+        }
+        config.running_sum_config.range_check(
+            layouter.namespace(|| "total"),
+            &batch.total,
+            self.amount_num_bits,
+        )?;
+
+        let mut public_inputs: Vec<_> = batch.b_public_key.iter().collect();
+        public_inputs.extend([
+            &batch.b_index,
+            &batch.total,
+            &batch.commitment,
+            &batch.now,
+            &batch.registry_root,
+        ]);
+        public_inputs.extend(batch.nullifiers.iter());
+        config.expose_public_inputs(&mut layouter, &public_inputs)?;
+
+        S::load_tables(&config.signature_config, &mut layouter)
+    }
+}
+
+/// Public inputs of [`ReceiptBatchCircuit`].
+///
+/// The instance column is laid out as
+/// `[b_public_key cells, b_index, total, commitment, now, registry_root, nullifiers]`,
+/// where the cells of the public key are given by
+/// [`ReceiptSignatureScheme::public_key_values`] and the nullifiers are in the
+/// order of the receipts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReceiptBatchPublicInputs<S: ReceiptSignatureScheme<F>, F: FieldExt> {
+    pub b_public_key: S::PublicKey,
+    pub b_index: F,
+    /// Sum of the receipt amounts.
+    pub total: F,
+    /// See [`commitment`].
+    pub commitment: F,
+    /// Time at which no receipt must have expired yet.
+    pub now: F,
+    /// Root of the account registry, see [`crate::merkle`].
+    pub registry_root: F,
+    /// See [`Receipt::nullifier`].
+    pub nullifiers: Vec<F>,
+}
+
+impl<E: CurveAffine, F: FieldExt> ReceiptBatchPublicInputs<Ecdsa<E>, F>
+where
+    P128Pow5T3: Spec<F, P128_WIDTH, P128_RATE>,
+{
+    /// Public inputs of the circuit returned by
+    /// [`ReceiptBatchCircuit::from_receipts`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is zero.
+    pub fn from_receipts<const N: usize>(
+        b_public_key: E,
+        receipts: &[Receipt<F>; N],
+        now: F,
+        nullifier_secret: F,
+        registry_root: F,
+    ) -> Self {
+        Self::from_receipts_with(b_public_key, receipts, now, nullifier_secret, registry_root)
+    }
+}
+
+impl<S: ReceiptSignatureScheme<F>, F: FieldExt> ReceiptBatchPublicInputs<S, F>
+where
+    P128Pow5T3: Spec<F, P128_WIDTH, P128_RATE>,
+{
+    /// Public inputs of the circuit returned by
+    /// [`ReceiptBatchCircuit::from_receipts_with`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is zero.
+    pub fn from_receipts_with<const N: usize>(
+        b_public_key: S::PublicKey,
+        receipts: &[Receipt<F>; N],
+        now: F,
+        nullifier_secret: F,
+        registry_root: F,
+    ) -> Self {
+        assert!(N > 0, "empty batch");
+        Self {
+            b_public_key,
+            b_index: receipts[0].b_index,
+            total: receipts
+                .iter()
+                .fold(F::zero(), |total, receipt| total + receipt.amount),
+            commitment: commitment(receipts),
+            now,
+            registry_root,
+            nullifiers: receipts
+                .iter()
+                .map(|receipt| receipt.nullifier(nullifier_secret))
+                .collect(),
+        }
+    }
+}
+
+impl<S: ReceiptSignatureScheme<F>, F: FieldExt> ReceiptBatchPublicInputs<S, F> {
+    /// Values of the batch instance column.
+    pub fn to_instance(&self) -> Vec<F> {
+        let mut instance = S::public_key_values(&self.b_public_key);
+        instance.push(self.b_index);
+        instance.push(self.total);
+        instance.push(self.commitment);
+        instance.push(self.now);
+        instance.push(self.registry_root);
+        instance.extend(self.nullifiers.iter().copied());
+        instance
+    }
+
+    /// Values of every instance column of the circuit, the first one being
+    /// the empty `MainGate` column.
+    pub fn instances(&self) -> Vec<Vec<F>> {
+        vec![vec![], self.to_instance()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2::{
+        arithmetic::{CurveAffine, Field, FieldExt},
+        circuit::Value,
+        dev::MockProver,
+        halo2curves::{
+            group::Curve,
+            pasta::{pallas, Fp as PastaFp, Fq},
+            secp256k1::Secp256k1Affine,
+        },
+    };
+    use rand_core::OsRng;
+
+    use super::{commitment, ReceiptBatchCircuit, ReceiptBatchPublicInputs};
+    use crate::{
+        ecdsa::Ecdsa,
+        merkle::{registry_leaf, scheme_registry_leaf, secret_commitment, IncrementalMerkleTree},
+        schnorr::{self, Schnorr},
+        Receipt, DEFAULT_AMOUNT_NUM_BITS, DEFAULT_TIMESTAMP_NUM_BITS, DEFAULT_WINDOW_SIZE,
+    };
+
+    /// Path and root of `leaf` registered as account 2 in a registry of depth
+    /// 8.
+    fn register(leaf: PastaFp) -> (Vec<PastaFp>, PastaFp) {
+        let mut registry = IncrementalMerkleTree::new(8);
+        registry.insert(PastaFp::random(OsRng));
+        registry.insert(PastaFp::random(OsRng));
+        let index = registry.insert(leaf);
+        (registry.path(index), registry.root())
+    }
+
+    #[test]
+    fn test_receipt_batch() {
+        type Scalar = <Secp256k1Affine as CurveAffine>::ScalarExt;
+
+        let g = Secp256k1Affine::generator();
+        let sk = Scalar::random(OsRng);
+        let pk = (g * sk).to_affine();
+        let now = PastaFp::from(1_600_000_000);
+        let nullifier_secret = PastaFp::random(OsRng);
+
+        let receipts = [
+            Receipt {
+                a_index: PastaFp::from(1),
+                b_index: PastaFp::from(2),
+                amount: PastaFp::from(100),
+                expires_by: PastaFp::from(1_700_000_000),
+            },
+            Receipt {
+                a_index: PastaFp::from(3),
+                b_index: PastaFp::from(2),
+                amount: PastaFp::from(250),
+                expires_by: PastaFp::from(1_700_000_600),
+            },
+        ];
+        let signatures = receipts.map(|receipt| receipt.sign::<Secp256k1Affine>(sk));
+        let (path, root) = register(registry_leaf(
+            PastaFp::from(2),
+            pk,
+            secret_commitment(nullifier_secret),
+        ));
+
+        let k = 20;
+        let circuit = ReceiptBatchCircuit::from_receipts(
+            pk,
+            &receipts,
+            &signatures,
+            now,
+            nullifier_secret,
+            &path,
+        );
+        let public_inputs =
+            ReceiptBatchPublicInputs::from_receipts(pk, &receipts, now, nullifier_secret, root);
+        assert_eq!(public_inputs.total, PastaFp::from(350));
+        // same nullifiers as the receipts redeemed alone
+        assert_eq!(
+            public_inputs.nullifiers,
+            receipts.map(|receipt| receipt.nullifier(nullifier_secret))
+        );
+
+        let prover = MockProver::run(k, &circuit, public_inputs.instances()).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // commitment depends on the order of the receipts
+        assert_ne!(
+            commitment(&receipts),
+            commitment(&[receipts[1], receipts[0]])
+        );
+
+        // public inputs that don't match the batch
+        for tampered_inputs in [
+            ReceiptBatchPublicInputs {
+                total: public_inputs.total + PastaFp::one(),
+                ..public_inputs.clone()
+            },
+            ReceiptBatchPublicInputs {
+                commitment: commitment(&[receipts[1], receipts[0]]),
+                ..public_inputs.clone()
+            },
+            ReceiptBatchPublicInputs {
+                b_index: PastaFp::from(3),
+                ..public_inputs.clone()
+            },
+            ReceiptBatchPublicInputs {
+                nullifiers: vec![public_inputs.nullifiers[1], public_inputs.nullifiers[0]],
+                ..public_inputs.clone()
+            },
+            ReceiptBatchPublicInputs {
+                registry_root: PastaFp::random(OsRng),
+                ..public_inputs.clone()
+            },
+        ] {
+            let prover = MockProver::run(k, &circuit, tampered_inputs.instances()).unwrap();
+            assert!(prover.verify().is_err());
+        }
+
+        // receipt of another payee, or tampered after signing
+        let other_payee = Receipt {
+            b_index: PastaFp::from(3),
+            ..receipts[1]
+        };
+        let mut tampered_signatures = circuit.clone();
+        tampered_signatures.signatures[1] = Value::known(other_payee.sign::<Secp256k1Affine>(sk));
+        let mut tampered_amounts = circuit.clone();
+        tampered_amounts.amounts[0] = Value::known(PastaFp::from(1_000));
+        let mut other_key = circuit.clone();
+        other_key.signatures[1] =
+            Value::known(receipts[1].sign::<Secp256k1Affine>(Scalar::random(OsRng)));
+        for tampered in [tampered_signatures, tampered_amounts, other_key] {
+            let prover = MockProver::run(k, &tampered, public_inputs.instances()).unwrap();
+            assert!(prover.verify().is_err());
+        }
+
+        // amounts that don't fit in `amount_num_bits`, even if signed
+        let wrapped_receipts = [
+            Receipt {
+                amount: -PastaFp::one(),
+                ..receipts[0]
+            },
+            Receipt {
+                amount: PastaFp::from(2),
+                ..receipts[1]
+            },
+        ];
+        let wrapped = ReceiptBatchCircuit::from_receipts(
+            pk,
+            &wrapped_receipts,
+            &wrapped_receipts.map(|receipt| receipt.sign::<Secp256k1Affine>(sk)),
+            now,
+            nullifier_secret,
+            &path,
+        );
+        let wrapped_inputs = ReceiptBatchPublicInputs::from_receipts(
+            pk,
+            &wrapped_receipts,
+            now,
+            nullifier_secret,
+            root,
+        );
+        assert_eq!(wrapped_inputs.total, PastaFp::one());
+        let prover = MockProver::run(k, &wrapped, wrapped_inputs.instances()).unwrap();
+        assert!(prover.verify().is_err());
+
+        // total that doesn't fit even though every amount does
+        let overflowing_receipts = receipts.map(|receipt| Receipt {
+            amount: PastaFp::from_u128((1 << 64) - 1),
+            ..receipt
+        });
+        let overflowing = ReceiptBatchCircuit::from_receipts(
+            pk,
+            &overflowing_receipts,
+            &overflowing_receipts.map(|receipt| receipt.sign::<Secp256k1Affine>(sk)),
+            now,
+            nullifier_secret,
+            &path,
+        );
+        let overflowing_inputs = ReceiptBatchPublicInputs::from_receipts(
+            pk,
+            &overflowing_receipts,
+            now,
+            nullifier_secret,
+            root,
+        );
+        let prover = MockProver::run(k, &overflowing, overflowing_inputs.instances()).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_empty_receipt_batch() {
+        let empty = ReceiptBatchCircuit::<Ecdsa<Secp256k1Affine>, PastaFp, 0> {
+            b_index: Value::known(PastaFp::from(2)),
+            b_public_key: Value::known(Secp256k1Affine::generator()),
+            b_registry_path: vec![Value::known(PastaFp::zero()); 8],
+            a_indices: [],
+            amounts: [],
+            expires_by: [],
+            signatures: [],
+            now: Value::known(PastaFp::from(1_600_000_000)),
+            nullifier_secret: Value::known(PastaFp::random(OsRng)),
+            timestamp_num_bits: DEFAULT_TIMESTAMP_NUM_BITS,
+            amount_num_bits: DEFAULT_AMOUNT_NUM_BITS,
+            scheme: Ecdsa::new(DEFAULT_WINDOW_SIZE),
+        };
+        assert!(MockProver::run(20, &empty, vec![vec![], vec![]]).is_err());
+    }
+
+    #[test]
+    fn test_schnorr_receipt_batch() {
+        let g = pallas::Affine::generator();
+        let sk = Fq::random(OsRng);
+        let pk = (g * sk).to_affine();
+        let now = PastaFp::from(1_600_000_000);
+        let nullifier_secret = PastaFp::random(OsRng);
+
+        let receipts = [1u64, 3].map(|a_index| Receipt {
+            a_index: PastaFp::from(a_index),
+            b_index: PastaFp::from(2),
+            amount: PastaFp::from(100 * a_index),
+            expires_by: PastaFp::from(1_700_000_000),
+        });
+        let signatures = receipts.map(|receipt| schnorr::sign(sk, receipt.hash()));
+        let (path, root) = register(scheme_registry_leaf::<Schnorr, _>(
+            PastaFp::from(2),
+            &pk,
+            secret_commitment(nullifier_secret),
+        ));
+
+        let k = 17;
+        let circuit = ReceiptBatchCircuit::from_receipts_with(
+            Schnorr,
+            pk,
+            &receipts,
+            &signatures,
+            now,
+            nullifier_secret,
+            &path,
+        );
+        let public_inputs = ReceiptBatchPublicInputs::<Schnorr, _>::from_receipts_with(
+            pk,
+            &receipts,
+            now,
+            nullifier_secret,
+            root,
+        );
+        assert_eq!(public_inputs.total, PastaFp::from(400));
+
+        let prover = MockProver::run(k, &circuit, public_inputs.instances()).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // signature by another key
+        let mut tampered = circuit.clone();
+        tampered.signatures[1] = Value::known(schnorr::sign(Fq::random(OsRng), receipts[1].hash()));
+        let prover = MockProver::run(k, &tampered, public_inputs.instances()).unwrap();
+        assert!(prover.verify().is_err());
+
+        // nullifier secret other than the registered one, with matching
+        // nullifiers
+        let other_secret = PastaFp::random(OsRng);
+        let other_inputs = ReceiptBatchPublicInputs::<Schnorr, _>::from_receipts_with(
+            pk,
+            &receipts,
+            now,
+            other_secret,
+            root,
+        );
+        let tampered = ReceiptBatchCircuit {
+            nullifier_secret: Value::known(other_secret),
+            ..circuit.clone()
+        };
+        let prover = MockProver::run(k, &tampered, other_inputs.instances()).unwrap();
+        assert!(prover.verify().is_err());
+
+        // receipt that has expired by `now`
+        let expired_receipts = [
+            receipts[0],
+            Receipt {
+                expires_by: now,
+                ..receipts[1]
+            },
+        ];
+        let expired = ReceiptBatchCircuit::from_receipts_with(
+            Schnorr,
+            pk,
+            &expired_receipts,
+            &expired_receipts.map(|receipt| schnorr::sign(sk, receipt.hash())),
+            now,
+            nullifier_secret,
+            &path,
+        );
+        let expired_inputs = ReceiptBatchPublicInputs::<Schnorr, _>::from_receipts_with(
+            pk,
+            &expired_receipts,
+            now,
+            nullifier_secret,
+            root,
+        );
+        let prover = MockProver::run(k, &expired, expired_inputs.instances()).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_repeated_receipt_batch() {
+        let g = pallas::Affine::generator();
+        let sk = Fq::random(OsRng);
+        let pk = (g * sk).to_affine();
+        let now = PastaFp::from(1_600_000_000);
+        let nullifier_secret = PastaFp::random(OsRng);
+
+        let receipt = Receipt {
+            a_index: PastaFp::from(1),
+            b_index: PastaFp::from(2),
+            amount: PastaFp::from(100),
+            expires_by: PastaFp::from(1_700_000_000),
+        };
+        let signature = schnorr::sign(sk, receipt.hash());
+        let (path, root) = register(scheme_registry_leaf::<Schnorr, _>(
+            PastaFp::from(2),
+            &pk,
+            secret_commitment(nullifier_secret),
+        ));
+
+        // the same signed receipt in both slots, settling twice its amount
+        let receipts = [receipt, receipt];
+        let circuit = ReceiptBatchCircuit::from_receipts_with(
+            Schnorr,
+            pk,
+            &receipts,
+            &[signature, signature],
+            now,
+            nullifier_secret,
+            &path,
+        );
+        let public_inputs = ReceiptBatchPublicInputs::<Schnorr, _>::from_receipts_with(
+            pk,
+            &receipts,
+            now,
+            nullifier_secret,
+            root,
+        );
+        assert_eq!(public_inputs.total, PastaFp::from(200));
+
+        let prover = MockProver::run(17, &circuit, public_inputs.instances()).unwrap();
+        assert!(prover.verify().is_err());
+    }
+}
@@ -0,0 +1,426 @@
+//! Payment-channel updates between `a_index` and `b_index`.
+//!
+//! Receipts of a channel carry the cumulative `amount` paid so far, so a
+//! receipt supersedes another one of the same channel iff its amount is
+//! strictly greater. [`ChannelUpdateCircuit`] proves that two receipts signed
+//! by `b_public_key` belong to the same channel and that the new one
+//! supersedes the old one, exposing the delta but neither amount.
+//!
+//! Receipts have no separate nonce, the cumulative amount orders them.
+
+use ff::PrimeFieldBits;
+use halo2::{
+    arithmetic::{CurveAffine, FieldExt},
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+use maingate::{MainGate, MainGateInstructions, RegionCtx};
+
+use crate::{
+    comparison::LessThanChip,
+    ecdsa::{Ecdsa, Signature},
+    poseidon::{Domain, P128Pow5T3, PoseidonChip, Spec, P128_RATE, P128_WIDTH},
+    signature::ReceiptSignatureScheme,
+    Receipt, ReceiptConfig, DEFAULT_AMOUNT_NUM_BITS, DEFAULT_WINDOW_SIZE,
+};
+
+/// Proves that `new_signature` and `old_signature` by `b_public_key` are
+/// valid for receipts `{a_index, b_index, new_amount, new_expires_by}` and
+/// `{a_index, b_index, old_amount, old_expires_by}` with
+/// `old_amount < new_amount`.
+///
+/// The signature scheme is `S`, e.g. [`Ecdsa`] over secp256k1 or
+/// [`crate::schnorr::Schnorr`] over Pallas, and `scheme` holds its layout
+/// parameters.
+///
+/// Both amounts are constrained to `amount_num_bits` bits, so the delta
+/// `new_amount - old_amount` is in `[1, 2^amount_num_bits)`.
+///
+/// Public inputs are described by [`ChannelUpdatePublicInputs`].
+#[derive(Clone, Debug, Default)]
+pub struct ChannelUpdateCircuit<S: ReceiptSignatureScheme<N>, N: FieldExt> {
+    pub a_index: Value<N>,
+    pub b_index: Value<N>,
+
+    pub old_amount: Value<N>,
+    pub old_expires_by: Value<N>,
+    pub old_signature: Value<S::Signature>,
+
+    pub new_amount: Value<N>,
+    pub new_expires_by: Value<N>,
+    pub new_signature: Value<S::Signature>,
+
+    pub b_public_key: Value<S::PublicKey>,
+
+    pub amount_num_bits: usize,
+
+    pub scheme: S,
+}
+
+impl<E: CurveAffine, N: FieldExt> ChannelUpdateCircuit<Ecdsa<E>, N> {
+    /// Circuit proving that `new` supersedes `old`, both signed with ECDSA by
+    /// `b_public_key`.
+    ///
+    /// Amount bit width and window size are set to their defaults and can be
+    /// overridden on the returned circuit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the receipts don't belong to the same channel.
+    pub fn from_receipts(
+        b_public_key: E,
+        old: &Receipt<N>,
+        old_signature: Signature<E>,
+        new: &Receipt<N>,
+        new_signature: Signature<E>,
+    ) -> Self {
+        Self::from_receipts_with(
+            Ecdsa::new(DEFAULT_WINDOW_SIZE),
+            b_public_key,
+            old,
+            old_signature,
+            new,
+            new_signature,
+        )
+    }
+}
+
+impl<S: ReceiptSignatureScheme<N>, N: FieldExt> ChannelUpdateCircuit<S, N> {
+    /// Like [`ChannelUpdateCircuit::from_receipts`], for signatures of scheme
+    /// `S` laid out by `scheme`.
+    pub fn from_receipts_with(
+        scheme: S,
+        b_public_key: S::PublicKey,
+        old: &Receipt<N>,
+        old_signature: S::Signature,
+        new: &Receipt<N>,
+        new_signature: S::Signature,
+    ) -> Self {
+        assert_eq!(
+            (old.a_index, old.b_index),
+            (new.a_index, new.b_index),
+            "receipts of different channels"
+        );
+
+        Self {
+            a_index: Value::known(new.a_index),
+            b_index: Value::known(new.b_index),
+            old_amount: Value::known(old.amount),
+            old_expires_by: Value::known(old.expires_by),
+            old_signature: Value::known(old_signature),
+            new_amount: Value::known(new.amount),
+            new_expires_by: Value::known(new.expires_by),
+            new_signature: Value::known(new_signature),
+            b_public_key: Value::known(b_public_key),
+            amount_num_bits: DEFAULT_AMOUNT_NUM_BITS,
+            scheme,
+        }
+    }
+
+    /// Parameters that change the layout of the circuit, and hence its keys,
+    /// without changing its constraint system.
+    pub fn shape_parameters(&self) -> Vec<u64> {
+        let mut parameters = vec![self.amount_num_bits as u64];
+        parameters.extend(self.scheme.shape_parameters());
+        parameters
+    }
+}
+
+impl<S: ReceiptSignatureScheme<N>, N: FieldExt + PrimeFieldBits> Circuit<N>
+    for ChannelUpdateCircuit<S, N>
+where
+    P128Pow5T3: Spec<N, P128_WIDTH, P128_RATE>,
+{
+    type Config = ReceiptConfig<N, S>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            amount_num_bits: self.amount_num_bits,
+            scheme: self.scheme,
+            ..Default::default()
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<N>) -> Self::Config {
+        ReceiptConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<N>,
+    ) -> Result<(), Error> {
+        let main_gate = MainGate::<N>::new(config.main_gate_config.clone());
+        let signature_chip = self
+            .scheme
+            .load_chip(&config.signature_config, &mut layouter)?;
+        let poseidon_chip = PoseidonChip::<N, P128Pow5T3, P128_WIDTH, P128_RATE>::new(
+            config.main_gate_config.clone(),
+        );
+
+        let (b_public_key, a_index, b_index, old_amount, new_amount, delta) = layouter
+            .assign_region(
+                || "verify receipts",
+                |mut region| {
+                    let offset = &mut 0;
+                    let ctx = &mut RegionCtx::new(&mut region, offset);
+
+                    // both receipts hash the same channel cells
+                    let a_index = main_gate.assign_value(ctx, self.a_index)?;
+                    let b_index = main_gate.assign_value(ctx, self.b_index)?;
+                    let b_public_key =
+                        S::assign_public_key(&signature_chip, ctx, self.b_public_key)?;
+
+                    let mut amounts = vec![];
+                    for (amount, expires_by, signature) in [
+                        (self.old_amount, self.old_expires_by, self.old_signature),
+                        (self.new_amount, self.new_expires_by, self.new_signature),
+                    ] {
+                        let amount = main_gate.assign_value(ctx, amount)?;
+                        let expires_by = main_gate.assign_value(ctx, expires_by)?;
+                        let receipt_tag = poseidon_chip.domain_tag(ctx, Domain::Receipt)?;
+                        let hash = poseidon_chip.hash(
+                            ctx,
+                            &[
+                                receipt_tag,
+                                a_index.clone(),
+                                b_index.clone(),
+                                amount.clone(),
+                                expires_by,
+                            ],
+                        )?;
+                        let signature = S::assign_signature(&signature_chip, ctx, signature)?;
+                        S::verify(&signature_chip, ctx, &b_public_key, &hash, &signature)?;
+                        amounts.push(amount);
+                    }
+                    let new_amount = amounts.pop().unwrap();
+                    let old_amount = amounts.pop().unwrap();
+
+                    let delta = main_gate.sub(ctx, &new_amount, &old_amount)?;
+
+                    Ok((
+                        S::public_key_cells(&b_public_key),
+                        a_index,
+                        b_index,
+                        old_amount,
+                        new_amount,
+                        delta,
+                    ))
+                },
+            )?;
+
+        let less_than_chip =
+            LessThanChip::new(config.main_gate_config.clone(), config.running_sum_config);
+        less_than_chip.assert_less_than(
+            layouter.namespace(|| "old_amount < new_amount"),
+            &old_amount,
+            &new_amount,
+            self.amount_num_bits,
+        )?;
+
+        let mut public_inputs: Vec<_> = b_public_key.iter().collect();
+        public_inputs.extend([&a_index, &b_index, &delta]);
+        config.expose_public_inputs(&mut layouter, &public_inputs)?;
+
+        S::load_tables(&config.signature_config, &mut layouter)
+    }
+}
+
+/// Public inputs of [`ChannelUpdateCircuit`].
+///
+/// The instance column is laid out as
+/// `[b_public_key cells, a_index, b_index, delta]`, where the cells of the
+/// public key are given by [`ReceiptSignatureScheme::public_key_values`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChannelUpdatePublicInputs<S: ReceiptSignatureScheme<N>, N: FieldExt> {
+    pub b_public_key: S::PublicKey,
+    pub a_index: N,
+    pub b_index: N,
+    /// Amount paid between the old and the new receipt.
+    pub delta: N,
+}
+
+impl<E: CurveAffine, N: FieldExt> ChannelUpdatePublicInputs<Ecdsa<E>, N> {
+    /// Public inputs of the circuit returned by
+    /// [`ChannelUpdateCircuit::from_receipts`].
+    pub fn from_receipts(b_public_key: E, old: &Receipt<N>, new: &Receipt<N>) -> Self {
+        Self::from_receipts_with(b_public_key, old, new)
+    }
+}
+
+impl<S: ReceiptSignatureScheme<N>, N: FieldExt> ChannelUpdatePublicInputs<S, N> {
+    /// Public inputs of the circuit returned by
+    /// [`ChannelUpdateCircuit::from_receipts_with`].
+    pub fn from_receipts_with(
+        b_public_key: S::PublicKey,
+        old: &Receipt<N>,
+        new: &Receipt<N>,
+    ) -> Self {
+        Self {
+            b_public_key,
+            a_index: new.a_index,
+            b_index: new.b_index,
+            delta: new.amount - old.amount,
+        }
+    }
+
+    /// Values of the channel instance column.
+    pub fn to_instance(&self) -> Vec<N> {
+        let mut instance = S::public_key_values(&self.b_public_key);
+        instance.push(self.a_index);
+        instance.push(self.b_index);
+        instance.push(self.delta);
+        instance
+    }
+
+    /// Values of every instance column of the circuit, the first one being
+    /// the empty `MainGate` column.
+    pub fn instances(&self) -> Vec<Vec<N>> {
+        vec![vec![], self.to_instance()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2::{
+        arithmetic::{CurveAffine, Field},
+        circuit::Value,
+        dev::MockProver,
+        halo2curves::{
+            group::Curve,
+            pasta::{pallas, Fp as PastaFp, Fq},
+            secp256k1::Secp256k1Affine,
+        },
+    };
+    use rand_core::OsRng;
+
+    use super::{ChannelUpdateCircuit, ChannelUpdatePublicInputs};
+    use crate::{
+        schnorr::{self, Schnorr},
+        Receipt,
+    };
+
+    #[test]
+    fn test_channel_update() {
+        type Scalar = <Secp256k1Affine as CurveAffine>::ScalarExt;
+
+        let g = Secp256k1Affine::generator();
+        let sk = Scalar::random(OsRng);
+        let pk = (g * sk).to_affine();
+
+        let old = Receipt {
+            a_index: PastaFp::from(1),
+            b_index: PastaFp::from(2),
+            amount: PastaFp::from(100),
+            expires_by: PastaFp::from(1_700_000_000),
+        };
+        let new = Receipt {
+            amount: PastaFp::from(250),
+            expires_by: PastaFp::from(1_700_000_600),
+            ..old
+        };
+
+        let k = 20;
+        let run = |old: &Receipt<PastaFp>, new: &Receipt<PastaFp>| {
+            let circuit = ChannelUpdateCircuit::from_receipts(
+                pk,
+                old,
+                old.sign::<Secp256k1Affine>(sk),
+                new,
+                new.sign::<Secp256k1Affine>(sk),
+            );
+            let public_inputs = ChannelUpdatePublicInputs::from_receipts(pk, old, new);
+            MockProver::run(k, &circuit, public_inputs.instances())
+                .unwrap()
+                .verify()
+        };
+
+        // new receipt supersedes the old one
+        assert_eq!(run(&old, &new), Ok(()));
+
+        let public_inputs = ChannelUpdatePublicInputs::from_receipts(pk, &old, &new);
+        assert_eq!(public_inputs.delta, PastaFp::from(150));
+
+        // amount must be strictly increasing
+        assert!(run(&old, &old).is_err());
+        assert!(run(&new, &old).is_err());
+
+        let circuit = ChannelUpdateCircuit::from_receipts(
+            pk,
+            &old,
+            old.sign::<Secp256k1Affine>(sk),
+            &new,
+            new.sign::<Secp256k1Affine>(sk),
+        );
+
+        // delta that doesn't match the receipts
+        let tampered_inputs = ChannelUpdatePublicInputs {
+            delta: public_inputs.delta + PastaFp::one(),
+            ..public_inputs
+        };
+        let prover = MockProver::run(k, &circuit, tampered_inputs.instances()).unwrap();
+        assert!(prover.verify().is_err());
+
+        // receipts of another channel, or tampered after signing
+        let other = Receipt {
+            a_index: PastaFp::from(3),
+            ..old
+        };
+        for tampered in [
+            ChannelUpdateCircuit {
+                old_signature: Value::known(other.sign::<Secp256k1Affine>(sk)),
+                ..circuit.clone()
+            },
+            ChannelUpdateCircuit {
+                old_amount: Value::known(PastaFp::from(50)),
+                ..circuit.clone()
+            },
+            ChannelUpdateCircuit {
+                new_signature: Value::known(new.sign::<Secp256k1Affine>(Scalar::random(OsRng))),
+                ..circuit.clone()
+            },
+        ] {
+            let prover = MockProver::run(k, &tampered, public_inputs.instances()).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_schnorr_channel_update() {
+        let g = pallas::Affine::generator();
+        let sk = Fq::random(OsRng);
+        let pk = (g * sk).to_affine();
+
+        let old = Receipt {
+            a_index: PastaFp::from(1),
+            b_index: PastaFp::from(2),
+            amount: PastaFp::from(100),
+            expires_by: PastaFp::from(1_700_000_000),
+        };
+        let new = Receipt {
+            amount: PastaFp::from(250),
+            ..old
+        };
+
+        let k = 16;
+        let run = |old: &Receipt<PastaFp>, new: &Receipt<PastaFp>| {
+            let circuit = ChannelUpdateCircuit::from_receipts_with(
+                Schnorr,
+                pk,
+                old,
+                schnorr::sign(sk, old.hash()),
+                new,
+                schnorr::sign(sk, new.hash()),
+            );
+            let public_inputs =
+                ChannelUpdatePublicInputs::<Schnorr, _>::from_receipts_with(pk, old, new);
+            MockProver::run(k, &circuit, public_inputs.instances())
+                .unwrap()
+                .verify()
+        };
+
+        assert_eq!(run(&old, &new), Ok(()));
+        assert!(run(&new, &old).is_err());
+    }
+}
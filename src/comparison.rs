@@ -0,0 +1,147 @@
+//! Comparison of `num_bits`-bit values.
+//!
+//! `a < b` holds iff `b - a - 1` is in range $[0, 2^{num\_bits})$, provided
+//! that both `a` and `b` are themselves in that range so that the difference
+//! can't wrap around the field modulus.
+
+use ff::PrimeFieldBits;
+use halo2::{arithmetic::FieldExt, circuit::Layouter, plonk::Error};
+use maingate::{AssignedValue, MainGate, MainGateConfig, MainGateInstructions, RegionCtx};
+
+use crate::range_check::RangeCheckConfig;
+
+#[derive(Clone, Debug)]
+pub struct LessThanChip<F: FieldExt + PrimeFieldBits, const WINDOW_NUM_BITS: usize> {
+    main_gate: MainGate<F>,
+    range_check_config: RangeCheckConfig<F, WINDOW_NUM_BITS>,
+}
+
+impl<F: FieldExt + PrimeFieldBits, const WINDOW_NUM_BITS: usize> LessThanChip<F, WINDOW_NUM_BITS> {
+    pub fn new(
+        main_gate_config: MainGateConfig,
+        range_check_config: RangeCheckConfig<F, WINDOW_NUM_BITS>,
+    ) -> Self {
+        Self {
+            main_gate: MainGate::new(main_gate_config),
+            range_check_config,
+        }
+    }
+
+    /// Constrains `a < b`, where both `a` and `b` are constrained to
+    /// `num_bits` bits.
+    pub fn assert_less_than(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedValue<F>,
+        b: &AssignedValue<F>,
+        num_bits: usize,
+    ) -> Result<(), Error> {
+        // b - a - 1
+        let diff = layouter.assign_region(
+            || "b - a - 1",
+            |mut region| {
+                let offset = &mut 0;
+                let ctx = &mut RegionCtx::new(&mut region, offset);
+                self.main_gate.sub_with_constant(ctx, b, a, -F::one())
+            },
+        )?;
+
+        self.range_check_config
+            .range_check(layouter.namespace(|| "a"), a, num_bits)?;
+        self.range_check_config
+            .range_check(layouter.namespace(|| "b"), b, num_bits)?;
+        self.range_check_config
+            .range_check(layouter.namespace(|| "b - a - 1"), &diff, num_bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::PrimeFieldBits;
+    use halo2::{
+        arithmetic::{Field, FieldExt},
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        halo2curves::pasta::Fp as PastaFp,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use maingate::{MainGate, MainGateConfig, MainGateInstructions, RegionCtx};
+
+    use super::LessThanChip;
+    use crate::range_check::RangeCheckConfig;
+
+    #[derive(Clone, Debug, Default)]
+    struct LessThanCircuit<F: FieldExt> {
+        a: Value<F>,
+        b: Value<F>,
+        num_bits: usize,
+    }
+
+    impl<F: FieldExt + PrimeFieldBits> Circuit<F> for LessThanCircuit<F> {
+        type Config = (MainGateConfig, RangeCheckConfig<F, 1>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                num_bits: self.num_bits,
+                ..Default::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let main_gate_config = MainGate::<F>::configure(meta);
+            let z = meta.advice_column();
+            let constants = meta.fixed_column();
+            let range_check_config = RangeCheckConfig::configure(meta, z, constants);
+            (main_gate_config, range_check_config)
+        }
+
+        fn synthesize(
+            &self,
+            (main_gate_config, range_check_config): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let main_gate = MainGate::<F>::new(main_gate_config.clone());
+            let less_than_chip = LessThanChip::new(main_gate_config, range_check_config);
+
+            let (a, b) = layouter.assign_region(
+                || "assign values",
+                |mut region| {
+                    let offset = &mut 0;
+                    let ctx = &mut RegionCtx::new(&mut region, offset);
+                    let a = main_gate.assign_value(ctx, self.a)?;
+                    let b = main_gate.assign_value(ctx, self.b)?;
+                    Ok((a, b))
+                },
+            )?;
+
+            less_than_chip.assert_less_than(layouter.namespace(|| "a < b"), &a, &b, self.num_bits)
+        }
+    }
+
+    #[test]
+    fn test_less_than() {
+        let run = |a: PastaFp, b: PastaFp, num_bits: usize| {
+            let circuit = LessThanCircuit {
+                a: Value::known(a),
+                b: Value::known(b),
+                num_bits,
+            };
+            let prover = MockProver::run(9, &circuit, vec![vec![]]).unwrap();
+            prover.verify()
+        };
+
+        for num_bits in [40, 64] {
+            let max = PastaFp::from_u128((1 << num_bits) - 1);
+
+            assert_eq!(run(PastaFp::from(1), PastaFp::from(2), num_bits), Ok(()));
+            assert_eq!(run(PastaFp::zero(), max, num_bits), Ok(()));
+            assert!(run(PastaFp::from(2), PastaFp::from(2), num_bits).is_err());
+            assert!(run(PastaFp::from(3), PastaFp::from(2), num_bits).is_err());
+            assert!(run(max, PastaFp::zero(), num_bits).is_err());
+            // values out of range could otherwise wrap around the modulus
+            assert!(run(PastaFp::from(1), max + PastaFp::one(), num_bits).is_err());
+            assert!(run(-PastaFp::one(), PastaFp::from(1), num_bits).is_err());
+        }
+    }
+}
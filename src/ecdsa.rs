@@ -0,0 +1,2456 @@
+//! ECDSA over any curve implementing `CurveAffine`, e.g. secp256k1.
+//!
+//! Message hashes are elements of the scalar field of the curve, as expected
+//! by the in-circuit `EcdsaChip`. [`EcdsaVerifyCircuit`] proves a single
+//! signature valid and is parameterized by the limb layout of the non-native
+//! arithmetic, while [`Ecdsa`] verifies receipt signatures over 4 limbs of 68
+//! bits. [`EcdsaSha256VerifyCircuit`] instead hashes the signed message with
+//! SHA-256 in-circuit, so that its public inputs commit to the message bytes,
+//! and [`EcdsaAddressVerifyCircuit`] exposes the Ethereum address of the
+//! signer in place of its public key. [`EcrecoverCircuit`] recovers the key
+//! from the signature as the `ecrecover` precompile of the EVM.
+//! [`EcdsaBatchVerifyCircuit`] verifies many signatures with a single
+//! multi-scalar multiplication.
+//!
+//! Both secp256k1 and P-256 have 256-bit fields, which fit the 4 limbs of 68
+//! bits of [`Ecdsa`]. `GeneralEccChip` only handles curves with `a = 0`, so
+//! over P-256 public keys are checked on the curve and signatures verified
+//! by this module, with additions only, see [`EcdsaSignatureChip::verify`].
+//! Recovery and batching require `a = 0`.
+
+use std::{marker::PhantomData, rc::Rc};
+
+use ::ecdsa::ecdsa::{AssignedEcdsaSig, AssignedPublicKey, EcdsaChip};
+use ecc::{integer::Range, AssignedPoint, EccConfig, GeneralEccChip};
+use ff::PrimeField;
+use halo2::{
+    arithmetic::{CurveAffine, Field, FieldExt},
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    halo2curves::group::Curve,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
+};
+use integer::{rns::Integer, AssignedInteger, IntegerInstructions, UnassignedInteger};
+use maingate::{
+    AssignedCondition, AssignedValue, MainGate, MainGateConfig, MainGateInstructions, RangeChip,
+    RangeConfig, RangeInstructions, RegionCtx, Term,
+};
+use num_bigint::BigUint;
+use num_traits::Num;
+use rand::rngs::OsRng;
+
+use crate::{
+    convert_field, decompose_limbs,
+    keccak::{keccak256, KeccakChip},
+    poseidon::{Domain, P128Pow5T3, PoseidonChip, Spec, P128_RATE, P128_WIDTH},
+    public_inputs::decompose_limbs_with,
+    sha256::{sha256, AssignedDigest, Sha256Chip},
+    signature::ReceiptSignatureScheme,
+    BIT_LEN_LIMB, NUMBER_OF_LIMBS,
+};
+
+/// `(r, s)`
+pub type Signature<C> = (<C as CurveAffine>::ScalarExt, <C as CurveAffine>::ScalarExt);
+
+/// Signs `msg_hash` with secret key `sk`.
+pub fn sign<C: CurveAffine>(sk: C::Scalar, msg_hash: C::Scalar) -> Signature<C> {
+    let g = C::generator();
+
+    loop {
+        let k = C::Scalar::random(OsRng);
+        let k_inv = k.invert().unwrap();
+
+        // r = x(k * G) mod n
+        let rpoint = (g * k).to_affine().coordinates().unwrap();
+        let r = convert_field::<C::Base, C::Scalar>(*rpoint.x());
+
+        // s = k^-1 * (msg_hash + r * sk)
+        let s = k_inv * (msg_hash + (r * sk));
+
+        if !bool::from(r.is_zero()) && !bool::from(s.is_zero()) {
+            return (r, s);
+        }
+    }
+}
+
+/// Verifies `signature` by `pk` over `msg_hash`.
+pub fn verify<C: CurveAffine>(pk: C, msg_hash: C::Scalar, signature: Signature<C>) -> bool {
+    let (r, s) = signature;
+    if bool::from(r.is_zero()) || bool::from(s.is_zero()) {
+        return false;
+    }
+
+    let s_inv = s.invert().unwrap();
+    let u_1 = msg_hash * s_inv;
+    let u_2 = r * s_inv;
+    let r_point = ((C::generator() * u_1) + (pk * u_2)).to_affine();
+
+    match Option::<_>::from(r_point.coordinates()) {
+        Some(coordinates) => convert_field::<C::Base, C::Scalar>(*coordinates.x()) == r,
+        // point at infinity
+        None => false,
+    }
+}
+
+/// Whether `s` of `signature` is at most `n / 2`, where `n` is the order of
+/// `C`. Of the two valid signatures `(r, s)` and `(r, n - s)`, only one has a
+/// low `s`.
+pub fn is_low_s<C: CurveAffine>(signature: Signature<C>) -> bool {
+    fe_to_big(&signature.1) <= half_order::<C::Scalar>()
+}
+
+/// `signature` with a low `s`, see [`is_low_s`].
+pub fn normalize_s<C: CurveAffine>(signature: Signature<C>) -> Signature<C> {
+    let (r, s) = signature;
+    if is_low_s::<C>(signature) {
+        (r, s)
+    } else {
+        (r, -s)
+    }
+}
+
+/// `(n - 1) / 2` for the modulus `n` of `F`.
+fn half_order<F: FieldExt>() -> BigUint {
+    (BigUint::from_str_radix(&F::MODULUS[2..], 16).unwrap() - 1u64) >> 1
+}
+
+/// SHA-256 digest of `message` read as a big-endian integer and reduced into
+/// the scalar field of `C`, i.e. the message hash of ECDSA over curves of
+/// 256-bit order.
+pub fn sha256_msg_hash<C: CurveAffine>(message: &[u8]) -> C::Scalar {
+    sha256(message).iter().fold(C::Scalar::zero(), |acc, byte| {
+        acc * C::Scalar::from(256) + C::Scalar::from(*byte as u64)
+    })
+}
+
+/// Ethereum address of `public_key`, i.e. the last 20 bytes of the Keccak-256
+/// digest of its uncompressed encoding without prefix.
+pub fn ethereum_address<C: CurveAffine>(public_key: C) -> [u8; 20] {
+    let coordinates = public_key.coordinates().unwrap();
+    let mut encoding = vec![];
+    for coordinate in [coordinates.x(), coordinates.y()] {
+        encoding.extend(coordinate.to_repr().as_ref().iter().rev());
+    }
+    keccak256(&encoding)[12..].try_into().unwrap()
+}
+
+/// Point `R` of a signature `(r, s)` with recovery id `v`, i.e. the point of
+/// `x = r` with an even `y` if `v = 27` and an odd one if `v = 28`.
+fn recovery_point<C: CurveAffine>(r: C::Scalar, v: u8) -> Option<C> {
+    if v != 27 && v != 28 {
+        return None;
+    }
+
+    let x = convert_field::<C::Scalar, C::Base>(r);
+    let y = Option::<C::Base>::from((x.square() * x + C::a() * x + C::b()).sqrt())?;
+    let y = if y.to_repr().as_ref()[0] & 1 == v - 27 {
+        y
+    } else {
+        -y
+    };
+    Option::from(C::from_xy(x, y))
+}
+
+/// Public key recovered from `signature` over `msg_hash` with recovery id
+/// `v`, following the `ecrecover` precompile of the EVM: the key is
+/// `r^-1 (s R - msg_hash G)` with `R` the point of `x = r` and a `y` of the
+/// parity of `v - 27`. Returns `None` where the precompile fails.
+pub fn recover<C: CurveAffine>(msg_hash: C::Scalar, signature: Signature<C>, v: u8) -> Option<C> {
+    let (r, s) = signature;
+    if bool::from(r.is_zero()) || bool::from(s.is_zero()) {
+        return None;
+    }
+
+    let r_point = recovery_point::<C>(r, v)?;
+    let r_inv = r.invert().unwrap();
+    let public_key = ((r_point * (s * r_inv)) - (C::generator() * (msg_hash * r_inv))).to_affine();
+    if bool::from(public_key.coordinates().is_none()) {
+        // point at infinity
+        return None;
+    }
+    Some(public_key)
+}
+
+/// Recovery id of `signature` by `pk` over `msg_hash`, see [`recover`].
+pub fn recovery_id<C: CurveAffine>(
+    pk: C,
+    msg_hash: C::Scalar,
+    signature: Signature<C>,
+) -> Option<u8> {
+    [27, 28]
+        .into_iter()
+        .find(|v| recover::<C>(msg_hash, signature, *v) == Some(pk))
+}
+
+/// Address returned by the `ecrecover` precompile of the EVM, see
+/// [`recover`].
+pub fn ecrecover<C: CurveAffine>(
+    msg_hash: C::Scalar,
+    signature: Signature<C>,
+    v: u8,
+) -> Option<[u8; 20]> {
+    recover::<C>(msg_hash, signature, v).map(ethereum_address)
+}
+
+fn fe_to_big<F: FieldExt>(fe: &F) -> BigUint {
+    BigUint::from_bytes_le(fe.to_repr().as_ref())
+}
+
+fn big_to_fe<F: FieldExt>(big: &BigUint) -> F {
+    F::from_str_vartime(&big.to_str_radix(10)[..]).unwrap()
+}
+
+/// Random auxiliary generator of the scalar multiplications. Its discrete
+/// logarithm is unknown to everyone.
+pub fn random_aux_generator<E: CurveAffine>() -> E {
+    E::CurveExt::random(OsRng).to_affine()
+}
+
+/// Columns and lookup tables for ECDSA verification over curve `E` with
+/// integers of `NUMBER_OF_LIMBS` limbs of `BIT_LEN_LIMB` bits.
+#[derive(Clone, Debug)]
+pub struct EcdsaConfig {
+    main_gate_config: MainGateConfig,
+    range_config: RangeConfig,
+}
+
+impl EcdsaConfig {
+    /// Configures the range chip next to `MainGate` for the limb layout of
+    /// the base and scalar fields of `E`.
+    pub fn configure<
+        E: CurveAffine,
+        N: FieldExt,
+        const NUMBER_OF_LIMBS: usize,
+        const BIT_LEN_LIMB: usize,
+    >(
+        meta: &mut ConstraintSystem<N>,
+        main_gate_config: &MainGateConfig,
+    ) -> Self {
+        let (rns_base, rns_scalar) = GeneralEccChip::<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>::rns();
+        let mut overflow_bit_lens: Vec<usize> = vec![];
+        overflow_bit_lens.extend(rns_base.overflow_lengths());
+        overflow_bit_lens.extend(rns_scalar.overflow_lengths());
+        let composition_bit_lens = vec![BIT_LEN_LIMB / NUMBER_OF_LIMBS];
+
+        let range_config = RangeChip::<N>::configure(
+            meta,
+            main_gate_config,
+            composition_bit_lens,
+            overflow_bit_lens,
+        );
+
+        EcdsaConfig {
+            main_gate_config: main_gate_config.clone(),
+            range_config,
+        }
+    }
+
+    pub fn main_gate_config(&self) -> &MainGateConfig {
+        &self.main_gate_config
+    }
+
+    pub fn ecc_chip_config(&self) -> EccConfig {
+        EccConfig::new(self.range_config.clone(), self.main_gate_config.clone())
+    }
+
+    /// Loads the lookup tables of the range chip, once every signature is
+    /// verified.
+    pub fn load_tables<N: FieldExt>(&self, layouter: &mut impl Layouter<N>) -> Result<(), Error> {
+        let range_chip = RangeChip::<N>::new(self.range_config.clone());
+        range_chip.load_composition_tables(layouter)?;
+        range_chip.load_overflow_tables(layouter)
+    }
+}
+
+/// Verifies ECDSA signatures over curve `E` in circuits over `N`, with
+/// integers of `NUMBER_OF_LIMBS` limbs of `BIT_LEN_LIMB` bits.
+#[derive(Clone, Debug)]
+pub struct EcdsaSignatureChip<
+    E: CurveAffine,
+    N: FieldExt,
+    const NUMBER_OF_LIMBS: usize,
+    const BIT_LEN_LIMB: usize,
+> {
+    main_gate: MainGate<N>,
+    ecc_chip: GeneralEccChip<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+    aux_generator: E,
+    window_size: usize,
+    /// Whether [`Ecdsa`] constrains signatures with [`Self::assert_strict`].
+    strict: bool,
+}
+
+impl<E: CurveAffine, N: FieldExt, const NUMBER_OF_LIMBS: usize, const BIT_LEN_LIMB: usize>
+    EcdsaSignatureChip<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>
+{
+    /// Builds the chip, assigning `aux_generator` and the auxiliary points of
+    /// scalar multiplications with windows of `window_size` bits.
+    pub fn load(
+        config: &EcdsaConfig,
+        aux_generator: E,
+        window_size: usize,
+        layouter: &mut impl Layouter<N>,
+    ) -> Result<Self, Error> {
+        Self::load_with_aux(config, aux_generator, window_size, &[1], layouter)
+    }
+
+    /// Builds the chip as [`Self::load`] does, also assigning the auxiliary
+    /// points of the multi-scalar multiplication of
+    /// [`Self::verify_batch`] over `batch_size` signatures.
+    pub fn load_batch(
+        config: &EcdsaConfig,
+        aux_generator: E,
+        window_size: usize,
+        batch_size: usize,
+        layouter: &mut impl Layouter<N>,
+    ) -> Result<Self, Error> {
+        Self::load_with_aux(
+            config,
+            aux_generator,
+            window_size,
+            &[1, 2 * batch_size + 1],
+            layouter,
+        )
+    }
+
+    /// Assigns the auxiliary points of multi-scalar multiplications of each
+    /// of `numbers_of_pairs` pairs.
+    fn load_with_aux(
+        config: &EcdsaConfig,
+        aux_generator: E,
+        window_size: usize,
+        numbers_of_pairs: &[usize],
+        layouter: &mut impl Layouter<N>,
+    ) -> Result<Self, Error> {
+        let mut ecc_chip =
+            GeneralEccChip::<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>::new(config.ecc_chip_config());
+
+        layouter.assign_region(
+            || "assign aux values",
+            |mut region| {
+                let offset = &mut 0;
+                let ctx = &mut RegionCtx::new(&mut region, offset);
+
+                // `GeneralEccChip` can't assign points of curves with `a != 0`,
+                // whose signatures are verified without it
+                if Self::has_zero_a() {
+                    ecc_chip.assign_aux_generator(ctx, Value::known(aux_generator))?;
+                    for number_of_pairs in numbers_of_pairs {
+                        ecc_chip.assign_aux(ctx, window_size, *number_of_pairs)?;
+                    }
+                }
+                Ok(())
+            },
+        )?;
+
+        Ok(Self {
+            main_gate: MainGate::new(config.main_gate_config.clone()),
+            ecc_chip,
+            aux_generator,
+            window_size,
+            strict: false,
+        })
+    }
+
+    /// Whether the curve equation of `E` is `y^2 = x^3 + b`, as assumed by
+    /// `GeneralEccChip` when checking points on the curve and doubling them.
+    /// It holds for secp256k1 but not for P-256, where `a = -3`.
+    fn has_zero_a() -> bool {
+        bool::from(E::a().is_zero())
+    }
+
+    pub fn assign_public_key(
+        &self,
+        ctx: &mut RegionCtx<'_, N>,
+        public_key: Value<E>,
+    ) -> Result<AssignedPoint<E::Base, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>, Error> {
+        if Self::has_zero_a() {
+            self.ecc_chip.assign_point(ctx, public_key)
+        } else {
+            self.assign_point_on_curve(ctx, public_key)
+        }
+    }
+
+    /// Assigns `point`, constrained to be on the curve `y^2 = x^3 + a x + b`.
+    fn assign_point_on_curve(
+        &self,
+        ctx: &mut RegionCtx<'_, N>,
+        point: Value<E>,
+    ) -> Result<AssignedPoint<E::Base, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>, Error> {
+        let base_chip = self.ecc_chip.base_field_chip();
+        let (rns_base, _) = GeneralEccChip::<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>::rns();
+        let rns_base = Rc::new(rns_base);
+
+        let (x, y) = point
+            .map(|point| {
+                let coordinates = point.coordinates().unwrap();
+                (*coordinates.x(), *coordinates.y())
+            })
+            .unzip();
+        let [x, y] = [x, y].map(|coordinate| {
+            UnassignedInteger::from(
+                coordinate.map(|coordinate| Integer::from_fe(coordinate, rns_base.clone())),
+            )
+        });
+        let x = base_chip.assign_integer(ctx, x, Range::Remainder)?;
+        let y = base_chip.assign_integer(ctx, y, Range::Remainder)?;
+
+        // (x^2 + a) x + b = y^2
+        let a = base_chip.assign_constant(ctx, E::a())?;
+        let b = base_chip.assign_constant(ctx, E::b())?;
+        let x_square = base_chip.square(ctx, &x)?;
+        let x_square_a = base_chip.add(ctx, &x_square, &a)?;
+        let rhs = base_chip.mul(ctx, &x_square_a, &x)?;
+        let rhs = base_chip.add(ctx, &rhs, &b)?;
+        let y_square = base_chip.square(ctx, &y)?;
+        base_chip.assert_equal(ctx, &rhs, &y_square)?;
+
+        Ok(AssignedPoint::new(x, y))
+    }
+
+    fn assign_constant_point(
+        &self,
+        ctx: &mut RegionCtx<'_, N>,
+        point: E,
+    ) -> Result<AssignedPoint<E::Base, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>, Error> {
+        let base_chip = self.ecc_chip.base_field_chip();
+        let coordinates = point.coordinates().unwrap();
+        Ok(AssignedPoint::new(
+            base_chip.assign_constant(ctx, *coordinates.x())?,
+            base_chip.assign_constant(ctx, *coordinates.y())?,
+        ))
+    }
+
+    /// Uncompressed encoding of `public_key` without prefix, i.e. the 32
+    /// big-endian bytes of `x` followed by those of `y`.
+    ///
+    /// Coordinates are reduced and constrained to be canonical, so that a
+    /// point has a single encoding whatever the limbs it is assigned with.
+    pub fn public_key_bytes(
+        &self,
+        ctx: &mut RegionCtx<'_, N>,
+        public_key: &AssignedPoint<E::Base, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+    ) -> Result<Vec<AssignedValue<N>>, Error> {
+        assert!(NUMBER_OF_LIMBS * BIT_LEN_LIMB >= 256);
+        let main_gate = &self.main_gate;
+        let base_chip = self.ecc_chip.base_field_chip();
+
+        let mut bytes = vec![];
+        for coordinate in [public_key.x(), public_key.y()] {
+            let coordinate = base_chip.reduce(ctx, coordinate)?;
+            self.assert_canonical(ctx, &coordinate)?;
+
+            // little-endian bits of the coordinate, which has 256 bits at most
+            let mut bits = vec![];
+            for (i, limb) in coordinate.limbs().iter().enumerate() {
+                let limb: AssignedValue<N> = limb.into();
+                let num_bits = BIT_LEN_LIMB.min(256usize.saturating_sub(i * BIT_LEN_LIMB));
+                if num_bits == 0 {
+                    main_gate.assert_zero(ctx, &limb)?;
+                } else {
+                    bits.extend(main_gate.to_bits(ctx, &limb, num_bits)?);
+                }
+            }
+
+            for byte in bits.chunks(8).rev() {
+                let terms: Vec<Term<N>> = byte
+                    .iter()
+                    .enumerate()
+                    .map(|(i, bit)| Term::Assigned(bit, N::from(1 << i)))
+                    .collect();
+                bytes.push(main_gate.compose(ctx, &terms, N::zero())?);
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Bytes of the Ethereum address of `public_key`, see [`ethereum_address`].
+    pub fn ethereum_address(
+        &self,
+        ctx: &mut RegionCtx<'_, N>,
+        keccak_chip: &KeccakChip<N>,
+        public_key: &AssignedPoint<E::Base, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+    ) -> Result<Vec<AssignedValue<N>>, Error> {
+        let encoding = self.public_key_bytes(ctx, public_key)?;
+        let digest = keccak_chip.digest(ctx, &encoding)?;
+        Ok(digest[12..].to_vec())
+    }
+
+    /// Assigns a scalar, e.g. a message hash.
+    pub fn assign_scalar(
+        &self,
+        ctx: &mut RegionCtx<'_, N>,
+        scalar: Value<E::Scalar>,
+    ) -> Result<AssignedInteger<E::Scalar, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>, Error> {
+        let scalar = self.ecc_chip.new_unassigned_scalar(scalar);
+        self.ecc_chip
+            .scalar_field_chip()
+            .assign_integer(ctx, scalar, Range::Remainder)
+    }
+
+    /// Assigns a SHA-256 `digest` read as a big-endian integer, whose limbs
+    /// are bound to the digest bits. The integer isn't reduced, which the
+    /// modular arithmetic of `verify` doesn't require.
+    pub fn assign_digest(
+        &self,
+        ctx: &mut RegionCtx<'_, N>,
+        digest: &AssignedDigest<N>,
+    ) -> Result<AssignedInteger<E::Scalar, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>, Error> {
+        assert!(NUMBER_OF_LIMBS * BIT_LEN_LIMB >= 256);
+        self.integer_from_bits(ctx, &digest.to_le_bits())
+    }
+
+    /// Scalar of little-endian `bits`, whose limbs are bound to the bits.
+    fn integer_from_bits(
+        &self,
+        ctx: &mut RegionCtx<'_, N>,
+        bits: &[AssignedCondition<N>],
+    ) -> Result<AssignedInteger<E::Scalar, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>, Error> {
+        assert!(bits.len() <= NUMBER_OF_LIMBS * BIT_LEN_LIMB);
+        let main_gate = &self.main_gate;
+
+        let mut limbs = bits
+            .chunks(BIT_LEN_LIMB)
+            .map(|bits| {
+                let mut coeff = N::one();
+                let terms: Vec<Term<N>> = bits
+                    .iter()
+                    .map(|bit| {
+                        let term = Term::Assigned(bit, coeff);
+                        coeff = coeff.double();
+                        term
+                    })
+                    .collect();
+                main_gate.compose(ctx, &terms, N::zero())
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        while limbs.len() < NUMBER_OF_LIMBS {
+            limbs.push(main_gate.assign_constant(ctx, N::zero())?);
+        }
+        self.integer_from_limbs(ctx, &limbs)
+    }
+
+    /// Scalar of `limbs`, from the least significant one, whose limbs are
+    /// bound to them.
+    fn integer_from_limbs(
+        &self,
+        ctx: &mut RegionCtx<'_, N>,
+        limbs: &[AssignedValue<N>],
+    ) -> Result<AssignedInteger<E::Scalar, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>, Error> {
+        let main_gate = &self.main_gate;
+
+        let (_, rns_scalar) = GeneralEccChip::<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>::rns();
+        let rns_scalar = Rc::new(rns_scalar);
+        let integer: Value<Vec<N>> = limbs.iter().map(|limb| limb.value().copied()).collect();
+        let integer =
+            integer.map(|limbs| Integer::from_limbs(&limbs.try_into().unwrap(), rns_scalar));
+        let integer = self.ecc_chip.scalar_field_chip().assign_integer(
+            ctx,
+            UnassignedInteger::from(integer),
+            Range::Remainder,
+        )?;
+
+        for (limb, composed) in integer.limbs().iter().zip(limbs.iter()) {
+            let limb: AssignedValue<N> = limb.into();
+            main_gate.assert_equal(ctx, &limb, composed)?;
+        }
+        Ok(integer)
+    }
+
+    pub fn assign_signature(
+        &self,
+        ctx: &mut RegionCtx<'_, N>,
+        signature: Value<Signature<E>>,
+    ) -> Result<AssignedEcdsaSig<E::Scalar, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>, Error> {
+        Ok(AssignedEcdsaSig {
+            r: self.assign_scalar(ctx, signature.map(|signature| signature.0))?,
+            s: self.assign_scalar(ctx, signature.map(|signature| signature.1))?,
+        })
+    }
+
+    /// Recovers the public key of `signature` over `msg_hash` with recovery
+    /// id `v`, following the `ecrecover` precompile of the EVM, see
+    /// [`recover`]. `r` and `s` are constrained to be canonical and non-zero
+    /// and `v` to be 27 or 28. Only for curves with `a = 0`.
+    pub fn recover(
+        &self,
+        ctx: &mut RegionCtx<'_, N>,
+        msg_hash: &AssignedInteger<E::Scalar, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+        signature: &AssignedEcdsaSig<E::Scalar, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+        v: &AssignedValue<N>,
+    ) -> Result<AssignedPoint<E::Base, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>, Error> {
+        assert!(Self::has_zero_a());
+        let main_gate = &self.main_gate;
+        let ecc_chip = &self.ecc_chip;
+        let scalar_chip = ecc_chip.scalar_field_chip();
+
+        for scalar in [&signature.r, &signature.s] {
+            self.assert_canonical(ctx, scalar)?;
+            scalar_chip.assert_not_zero(ctx, scalar)?;
+        }
+
+        // `R` shares its limbs of `x` with `r`, which is less than the modulus
+        // of the base field, and its `y` is the square root of the curve
+        // equation of the parity of `v - 27`
+        let r_limbs: Vec<AssignedValue<N>> =
+            signature.r.limbs().iter().map(|limb| limb.into()).collect();
+        let r_point = Self::integer_value(&signature.r)
+            .zip(v.value())
+            .map(|(r, v)| {
+                recovery_point::<E>(big_to_fe(&r), v.get_lower_128() as u8)
+                    .unwrap_or_else(E::generator)
+            });
+        let r_point = ecc_chip.assign_point(ctx, r_point)?;
+        for (x, r) in r_point.x().limbs().iter().zip(r_limbs.iter()) {
+            let x: AssignedValue<N> = x.into();
+            main_gate.assert_equal(ctx, &x, r)?;
+        }
+        self.assert_canonical(ctx, r_point.y())?;
+        let y: AssignedValue<N> = (&r_point.y().limbs()[0]).into();
+        let parity = main_gate.to_bits(ctx, &y, BIT_LEN_LIMB)?.remove(0);
+        let expected_v = main_gate.add_constant(ctx, &parity, N::from(27))?;
+        main_gate.assert_equal(ctx, &expected_v, v)?;
+
+        // Q = u1 G + u2 R with u1 = -msg_hash / r and u2 = s / r
+        let (r_inv, _) = scalar_chip.invert(ctx, &signature.r)?;
+        let u1 = scalar_chip.mul(ctx, msg_hash, &r_inv)?;
+        let u1 = scalar_chip.neg(ctx, &u1)?;
+        let u2 = scalar_chip.mul(ctx, &signature.s, &r_inv)?;
+        let generator = ecc_chip.assign_constant(ctx, E::generator())?;
+        let u1_g = ecc_chip.mul(ctx, &generator, &u1, self.window_size)?;
+        let u2_r = ecc_chip.mul(ctx, &r_point, &u2, self.window_size)?;
+        ecc_chip.add(ctx, &u1_g, &u2_r)
+    }
+
+    /// Constrains `signatures[i]` by `public_keys[i]` over `msg_hashes[i]` to
+    /// be valid for every `i`, in a single multi-scalar multiplication.
+    ///
+    /// With `u1 = msg_hash / s` and `u2 = r / s`, a signature is valid iff
+    /// `u1 G + u2 Q + R' = 0` for a point `R'` of `x = r`, witnessed as
+    /// `-(u1 G + u2 Q)`. Checks are combined with the powers `c^i` of a
+    /// challenge of 128 bits, hashed with Poseidon from every input and `R'`,
+    /// into `(1 + Σ c^i u1_i) G + Σ c^i u2_i Q_i + Σ c^i R'_i = G`. The
+    /// doublings are shared by all `2 * batch_size + 1` points, so each more
+    /// signature costs two additions per window rather than the two scalar
+    /// multiplications of [`Self::verify`].
+    ///
+    /// Each signature adds the same rows: two non-zero checks, one inversion,
+    /// five multiplications and one addition in the scalar field, the
+    /// witnessed `R'`, 24 Poseidon hashes of the challenge chain, and two
+    /// points of the multi-scalar multiplication.
+    /// Rows of a batch of `n` signatures are thus `b + n * m` for a fixed `b`,
+    /// and over secp256k1 with windows of 2 bits the slope `m` is below the
+    /// rows `v` of one [`Self::verify`]:
+    ///
+    /// | signatures | `verify_batch` | independent `verify` |
+    /// |-----------:|---------------:|---------------------:|
+    /// |          1 |        `b + m` |                  `v` |
+    /// |          2 |       `b + 2m` |                 `2v` |
+    /// |          3 |       `b + 3m` |                 `3v` |
+    /// |          4 |       `b + 4m` |                 `4v` |
+    ///
+    /// Batches of three or more take fewer rows than independent
+    /// verifications, see `test_batch_row_count`.
+    ///
+    /// The chip must be loaded with [`Self::load_batch`] for
+    /// `signatures.len()` signatures. Only for curves with `a = 0`.
+    pub fn verify_batch(
+        &self,
+        ctx: &mut RegionCtx<'_, N>,
+        poseidon_chip: &PoseidonChip<N, P128Pow5T3, P128_WIDTH, P128_RATE>,
+        public_keys: &[AssignedPoint<E::Base, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>],
+        msg_hashes: &[AssignedInteger<E::Scalar, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>],
+        signatures: &[AssignedEcdsaSig<E::Scalar, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>],
+    ) -> Result<(), Error>
+    where
+        P128Pow5T3: Spec<N, P128_WIDTH, P128_RATE>,
+    {
+        assert!(Self::has_zero_a());
+        assert_eq!(public_keys.len(), signatures.len());
+        assert_eq!(msg_hashes.len(), signatures.len());
+        let main_gate = &self.main_gate;
+        let ecc_chip = &self.ecc_chip;
+        let scalar_chip = ecc_chip.scalar_field_chip();
+
+        let mut checks = vec![];
+        for ((public_key, msg_hash), signature) in public_keys
+            .iter()
+            .zip(msg_hashes.iter())
+            .zip(signatures.iter())
+        {
+            scalar_chip.assert_not_zero(ctx, &signature.r)?;
+            scalar_chip.assert_not_zero(ctx, &signature.s)?;
+            let (s_inv, _) = scalar_chip.invert(ctx, &signature.s)?;
+            let u1 = scalar_chip.mul(ctx, msg_hash, &s_inv)?;
+            let u2 = scalar_chip.mul(ctx, &signature.r, &s_inv)?;
+
+            let r_point = Self::integer_value(public_key.x())
+                .zip(Self::integer_value(public_key.y()))
+                .zip(Self::integer_value(msg_hash))
+                .zip(Self::integer_value(&signature.r))
+                .zip(Self::integer_value(&signature.s))
+                .map(|((((x, y), msg_hash), r), s)| {
+                    let public_key = Option::<E>::from(E::from_xy(big_to_fe(&x), big_to_fe(&y)));
+                    let s_inv = Option::<E::Scalar>::from(big_to_fe::<E::Scalar>(&s).invert());
+                    let r_point = public_key.zip(s_inv).map(|(public_key, s_inv)| {
+                        let u1 = big_to_fe::<E::Scalar>(&msg_hash) * s_inv;
+                        let u2 = big_to_fe::<E::Scalar>(&r) * s_inv;
+                        (-(E::generator() * u1 + public_key * u2)).to_affine()
+                    });
+                    // invalid signatures fail the final check anyway
+                    r_point
+                        .filter(|r_point| bool::from(r_point.coordinates().is_some()))
+                        .unwrap_or_else(E::generator)
+                });
+            let r_point = ecc_chip.assign_point(ctx, r_point)?;
+            for (x, r) in r_point.x().limbs().iter().zip(signature.r.limbs().iter()) {
+                let x: AssignedValue<N> = x.into();
+                let r: AssignedValue<N> = r.into();
+                main_gate.assert_equal(ctx, &x, &r)?;
+            }
+
+            checks.push((public_key, msg_hash, signature, r_point, u1, u2));
+        }
+
+        // Fiat-Shamir challenge, chaining every limb of the checks
+        let mut challenge = main_gate.assign_constant(ctx, N::zero())?;
+        for (public_key, msg_hash, signature, r_point, _, _) in checks.iter() {
+            for integer_limbs in [
+                public_key.x().limbs(),
+                public_key.y().limbs(),
+                msg_hash.limbs(),
+                signature.r.limbs(),
+                signature.s.limbs(),
+                r_point.y().limbs(),
+            ] {
+                for limb in integer_limbs.iter() {
+                    let limb: AssignedValue<N> = limb.into();
+                    let tag = poseidon_chip.domain_tag(ctx, Domain::EcdsaBatchChallenge)?;
+                    challenge = poseidon_chip.hash(ctx, &[tag, challenge, limb])?;
+                }
+            }
+        }
+
+        // low 128 bits of the challenge
+        let (low, high) = challenge
+            .value()
+            .map(|challenge| {
+                let challenge = fe_to_big(challenge);
+                let low = &challenge % (BigUint::from(1u64) << 128);
+                (big_to_fe::<N>(&low), big_to_fe::<N>(&(challenge >> 128)))
+            })
+            .unzip();
+        let low = main_gate.assign_value(ctx, low)?;
+        let high = main_gate.assign_value(ctx, high)?;
+        let composed = main_gate.compose(
+            ctx,
+            &[
+                Term::Assigned(&low, N::one()),
+                Term::Assigned(&high, big_to_fe(&(BigUint::from(1u64) << 128))),
+            ],
+            N::zero(),
+        )?;
+        main_gate.assert_equal(ctx, &composed, &challenge)?;
+        main_gate.to_bits(ctx, &high, N::NUM_BITS as usize - 128)?;
+        let low_bits = main_gate.to_bits(ctx, &low, 128)?;
+        let challenge = self.integer_from_bits(ctx, &low_bits)?;
+
+        let generator = ecc_chip.assign_constant(ctx, E::generator())?;
+        let mut coefficient = scalar_chip.assign_constant(ctx, E::Scalar::one())?;
+        let mut generator_scalar = coefficient.clone();
+        let mut pairs = vec![];
+        for (public_key, _, _, r_point, u1, u2) in checks.into_iter() {
+            let c_u1 = scalar_chip.mul(ctx, &coefficient, &u1)?;
+            generator_scalar = scalar_chip.add(ctx, &generator_scalar, &c_u1)?;
+            pairs.push((public_key.clone(), scalar_chip.mul(ctx, &coefficient, &u2)?));
+            pairs.push((r_point, coefficient.clone()));
+            coefficient = scalar_chip.mul(ctx, &coefficient, &challenge)?;
+        }
+        let generator_scalar = scalar_chip.reduce(ctx, &generator_scalar)?;
+        pairs.push((generator.clone(), generator_scalar));
+
+        let sum = ecc_chip.mul_batch_1d_horizontal(ctx, pairs, self.window_size)?;
+        ecc_chip.assert_equal(ctx, &sum, &generator)
+    }
+
+    /// Value of `integer`, from the values of its limbs.
+    fn integer_value<W: FieldExt>(
+        integer: &AssignedInteger<W, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+    ) -> Value<BigUint> {
+        let limbs: Value<Vec<N>> = integer
+            .limbs()
+            .iter()
+            .map(|limb| {
+                let limb: AssignedValue<N> = limb.into();
+                limb.value().copied()
+            })
+            .collect();
+        limbs.map(|limbs| {
+            limbs.iter().rev().fold(BigUint::from(0u64), |acc, limb| {
+                (acc << BIT_LEN_LIMB) + fe_to_big(limb)
+            })
+        })
+    }
+
+    /// Constrains `signature` to be the canonical one of its two malleable
+    /// forms, i.e. `0 < r < n` and `0 < s <= n / 2` for the order `n` of `E`,
+    /// see [`is_low_s`]. Otherwise `(r, n - s)` is valid whenever `(r, s)` is.
+    pub fn assert_strict(
+        &self,
+        ctx: &mut RegionCtx<'_, N>,
+        signature: &AssignedEcdsaSig<E::Scalar, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+    ) -> Result<(), Error> {
+        let scalar_chip = self.ecc_chip.scalar_field_chip();
+
+        self.assert_canonical(ctx, &signature.r)?;
+        scalar_chip.assert_not_zero(ctx, &signature.r)?;
+        self.assert_at_most(ctx, &signature.s, &half_order::<E::Scalar>())?;
+        scalar_chip.assert_not_zero(ctx, &signature.s)
+    }
+
+    /// Constrains `integer` to be less than the modulus of `W`.
+    fn assert_canonical<W: FieldExt>(
+        &self,
+        ctx: &mut RegionCtx<'_, N>,
+        integer: &AssignedInteger<W, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+    ) -> Result<(), Error> {
+        let max = BigUint::from_str_radix(&W::MODULUS[2..], 16).unwrap() - 1u64;
+        self.assert_at_most(ctx, integer, &max)
+    }
+
+    /// Constrains the integer of the limbs of `integer` to be at most `max`.
+    ///
+    /// The low and high halves of its limbs each fit in a native element and
+    /// are subtracted from those of `max` with a borrow, both differences
+    /// being constrained to be non-negative.
+    fn assert_at_most<W: FieldExt>(
+        &self,
+        ctx: &mut RegionCtx<'_, N>,
+        integer: &AssignedInteger<W, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+        max: &BigUint,
+    ) -> Result<(), Error> {
+        let main_gate = &self.main_gate;
+
+        let split = NUMBER_OF_LIMBS / 2;
+        let low_num_bits = split * BIT_LEN_LIMB;
+        let high_num_bits = max.bits() as usize - low_num_bits;
+        assert!(low_num_bits.max(high_num_bits) < N::NUM_BITS as usize - 1);
+        let max_low = max % (BigUint::from(1u64) << low_num_bits);
+        let max_high = max >> low_num_bits;
+
+        let limbs: Vec<AssignedValue<N>> = integer.limbs().iter().map(|limb| limb.into()).collect();
+        let low = self.compose_limbs(ctx, &limbs[..split])?;
+        let high = self.compose_limbs(ctx, &limbs[split..])?;
+
+        let borrow = low.value().map(|low| {
+            if fe_to_big(low) > max_low {
+                N::one()
+            } else {
+                N::zero()
+            }
+        });
+        let borrow = main_gate.assign_value(ctx, borrow)?;
+        main_gate.to_bits(ctx, &borrow, 1)?;
+
+        // max_low - low + borrow * 2^low_num_bits
+        let low_difference = main_gate.compose(
+            ctx,
+            &[
+                Term::Assigned(&low, -N::one()),
+                Term::Assigned(&borrow, big_to_fe(&(BigUint::from(1u64) << low_num_bits))),
+            ],
+            big_to_fe(&max_low),
+        )?;
+        main_gate.to_bits(ctx, &low_difference, low_num_bits)?;
+        // max_high - high - borrow
+        let high_difference = main_gate.compose(
+            ctx,
+            &[
+                Term::Assigned(&high, -N::one()),
+                Term::Assigned(&borrow, -N::one()),
+            ],
+            big_to_fe(&max_high),
+        )?;
+        main_gate.to_bits(ctx, &high_difference, high_num_bits)?;
+
+        Ok(())
+    }
+
+    /// Native value of consecutive `limbs`, from the least significant one.
+    fn compose_limbs(
+        &self,
+        ctx: &mut RegionCtx<'_, N>,
+        limbs: &[AssignedValue<N>],
+    ) -> Result<AssignedValue<N>, Error> {
+        let terms: Vec<Term<N>> = limbs
+            .iter()
+            .enumerate()
+            .map(|(i, limb)| {
+                Term::Assigned(
+                    limb,
+                    big_to_fe(&(BigUint::from(1u64) << (i * BIT_LEN_LIMB))),
+                )
+            })
+            .collect();
+        self.main_gate.compose(ctx, &terms, N::zero())
+    }
+
+    /// Constrains `signature` by `public_key` over `msg_hash` to be valid.
+    ///
+    /// Over curves with `a != 0`, which `EcdsaChip` doesn't handle, scalar
+    /// multiplications are made of additions with `aux_generator` rather than
+    /// windowed.
+    pub fn verify(
+        &self,
+        ctx: &mut RegionCtx<'_, N>,
+        public_key: &AssignedPoint<E::Base, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+        msg_hash: &AssignedInteger<E::Scalar, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+        signature: &AssignedEcdsaSig<E::Scalar, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+    ) -> Result<(), Error> {
+        if !Self::has_zero_a() {
+            return self.verify_with_additions(ctx, public_key, msg_hash, signature);
+        }
+
+        let ecdsa_chip = EcdsaChip::new(self.ecc_chip.clone());
+        ecdsa_chip.verify(
+            ctx,
+            signature,
+            &AssignedPublicKey {
+                point: public_key.clone(),
+            },
+            msg_hash,
+        )
+    }
+
+    /// Constrains `signature` to be valid as [`Self::verify`] does, for curves
+    /// of any `a` such as P-256.
+    ///
+    /// `u1 G + u2 Q` is computed by double-and-add over the bits of both
+    /// scalars at once, doubling `P` and adding `T` as `(P + T) + P` so that
+    /// only additions, which don't depend on `a`, are used. Every `T` includes
+    /// the auxiliary generator `A`, which keeps the incomplete additions away
+    /// from the identity, and the accumulated multiple of `A` is subtracted at
+    /// the end.
+    fn verify_with_additions(
+        &self,
+        ctx: &mut RegionCtx<'_, N>,
+        public_key: &AssignedPoint<E::Base, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+        msg_hash: &AssignedInteger<E::Scalar, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+        signature: &AssignedEcdsaSig<E::Scalar, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>,
+    ) -> Result<(), Error> {
+        let main_gate = &self.main_gate;
+        let ecc_chip = &self.ecc_chip;
+        let scalar_chip = ecc_chip.scalar_field_chip();
+
+        scalar_chip.assert_not_zero(ctx, &signature.r)?;
+        scalar_chip.assert_not_zero(ctx, &signature.s)?;
+        let (s_inv, _) = scalar_chip.invert(ctx, &signature.s)?;
+        let u1 = scalar_chip.mul(ctx, msg_hash, &s_inv)?;
+        let u2 = scalar_chip.mul(ctx, &signature.r, &s_inv)?;
+
+        // little-endian bits of the limbs of the reduced scalars
+        let mut bits = vec![];
+        for scalar in [&u1, &u2] {
+            let mut scalar_bits = vec![];
+            for limb in scalar.limbs().iter() {
+                let limb: AssignedValue<N> = limb.into();
+                scalar_bits.extend(main_gate.to_bits(ctx, &limb, BIT_LEN_LIMB)?);
+            }
+            bits.push(scalar_bits);
+        }
+
+        // `A + b1 G + b2 Q` for the bits `b1` of `u1` and `b2` of `u2`
+        let aux = self.aux_generator;
+        let aux_point = self.assign_constant_point(ctx, aux)?;
+        let aux_g = self.assign_constant_point(ctx, (aux + E::generator()).to_affine())?;
+        let aux_q = ecc_chip.add(ctx, &aux_point, public_key)?;
+        let aux_g_q = ecc_chip.add(ctx, &aux_g, public_key)?;
+
+        // starting from `2 A` rather than `A`, which would add `A + A` first
+        let mut acc = self.assign_constant_point(ctx, (aux * E::Scalar::from(2)).to_affine())?;
+        for (b1, b2) in bits[0].iter().zip(bits[1].iter()).rev() {
+            let without_q = ecc_chip.select(ctx, b1, &aux_g, &aux_point)?;
+            let with_q = ecc_chip.select(ctx, b1, &aux_g_q, &aux_q)?;
+            let t = ecc_chip.select(ctx, b2, &with_q, &without_q)?;
+            let acc_t = ecc_chip.add(ctx, &acc, &t)?;
+            acc = ecc_chip.add(ctx, &acc_t, &acc)?;
+        }
+
+        // after `n` steps, `acc = (2^(n + 1) + 2^n - 1) A + u1 G + u2 Q`
+        let two_n = E::Scalar::from(2).pow_vartime([bits[0].len() as u64]);
+        let correction = -(aux * (two_n.double() + two_n - E::Scalar::one()));
+        let correction = self.assign_constant_point(ctx, correction.to_affine())?;
+        let point = ecc_chip.add(ctx, &acc, &correction)?;
+
+        // `x` of the point, canonical in the base field, equals `r` in the
+        // scalar field
+        let x = ecc_chip.base_field_chip().reduce(ctx, point.x())?;
+        self.assert_canonical(ctx, &x)?;
+        let limbs: Vec<AssignedValue<N>> = x.limbs().iter().map(|limb| limb.into()).collect();
+        let x = self.integer_from_limbs(ctx, &limbs)?;
+        scalar_chip.assert_equal(ctx, &x, &signature.r)
+    }
+}
+
+/// Proves that `signature` by `public_key` over `msg_hash` is a valid ECDSA
+/// signature over curve `E`, in a circuit over `N` with integers of
+/// `NUMBER_OF_LIMBS` limbs of `BIT_LEN_LIMB` bits.
+///
+/// `aux_generator` must have an unknown discrete logarithm, see
+/// [`random_aux_generator`], and `window_size` is the window of the scalar
+/// multiplications. Neither changes the constraint system, unlike `strict`
+/// which rejects the malleable form of signatures, see
+/// [`EcdsaSignatureChip::assert_strict`].
+#[derive(Clone, Debug, Default)]
+pub struct EcdsaVerifyCircuit<
+    E: CurveAffine,
+    N: FieldExt,
+    const NUMBER_OF_LIMBS: usize,
+    const BIT_LEN_LIMB: usize,
+> {
+    pub public_key: Value<E>,
+    pub signature: Value<Signature<E>>,
+    pub msg_hash: Value<E::Scalar>,
+
+    pub aux_generator: E,
+    pub window_size: usize,
+    pub strict: bool,
+    _marker: PhantomData<N>,
+}
+
+impl<E: CurveAffine, N: FieldExt, const NUMBER_OF_LIMBS: usize, const BIT_LEN_LIMB: usize>
+    EcdsaVerifyCircuit<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>
+{
+    pub fn new(
+        public_key: Value<E>,
+        signature: Value<Signature<E>>,
+        msg_hash: Value<E::Scalar>,
+        aux_generator: E,
+        window_size: usize,
+    ) -> Self {
+        Self {
+            public_key,
+            signature,
+            msg_hash,
+            aux_generator,
+            window_size,
+            strict: false,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<E: CurveAffine, N: FieldExt, const NUMBER_OF_LIMBS: usize, const BIT_LEN_LIMB: usize>
+    Circuit<N> for EcdsaVerifyCircuit<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>
+{
+    type Config = EcdsaConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            strict: self.strict,
+            ..Self::new(
+                Value::unknown(),
+                Value::unknown(),
+                Value::unknown(),
+                self.aux_generator,
+                self.window_size,
+            )
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<N>) -> Self::Config {
+        let main_gate_config = MainGate::<N>::configure(meta);
+        EcdsaConfig::configure::<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>(meta, &main_gate_config)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<N>,
+    ) -> Result<(), Error> {
+        let chip = EcdsaSignatureChip::<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>::load(
+            &config,
+            self.aux_generator,
+            self.window_size,
+            &mut layouter,
+        )?;
+
+        layouter.assign_region(
+            || "verify ecdsa",
+            |mut region| {
+                let offset = &mut 0;
+                let ctx = &mut RegionCtx::new(&mut region, offset);
+
+                let public_key = chip.assign_public_key(ctx, self.public_key)?;
+                let signature = chip.assign_signature(ctx, self.signature)?;
+                let msg_hash = chip.assign_scalar(ctx, self.msg_hash)?;
+                if self.strict {
+                    chip.assert_strict(ctx, &signature)?;
+                }
+                chip.verify(ctx, &public_key, &msg_hash, &signature)
+            },
+        )?;
+
+        config.load_tables(&mut layouter)
+    }
+}
+
+/// Proves that `signatures[i]` by `public_keys[i]` over `msg_hashes[i]` is a
+/// valid ECDSA signature over curve `E` for every `i`, see
+/// [`EcdsaSignatureChip::verify_batch`]. Parameters are those of
+/// [`EcdsaVerifyCircuit`] and the batch size changes the constraint system.
+#[derive(Clone, Debug, Default)]
+pub struct EcdsaBatchVerifyCircuit<
+    E: CurveAffine,
+    N: FieldExt,
+    const NUMBER_OF_LIMBS: usize,
+    const BIT_LEN_LIMB: usize,
+> {
+    pub public_keys: Vec<Value<E>>,
+    pub signatures: Vec<Value<Signature<E>>>,
+    pub msg_hashes: Vec<Value<E::Scalar>>,
+
+    pub aux_generator: E,
+    pub window_size: usize,
+    _marker: PhantomData<N>,
+}
+
+impl<E: CurveAffine, N: FieldExt, const NUMBER_OF_LIMBS: usize, const BIT_LEN_LIMB: usize>
+    EcdsaBatchVerifyCircuit<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>
+{
+    pub fn new(
+        public_keys: Vec<Value<E>>,
+        signatures: Vec<Value<Signature<E>>>,
+        msg_hashes: Vec<Value<E::Scalar>>,
+        aux_generator: E,
+        window_size: usize,
+    ) -> Self {
+        assert_eq!(public_keys.len(), signatures.len());
+        assert_eq!(msg_hashes.len(), signatures.len());
+        Self {
+            public_keys,
+            signatures,
+            msg_hashes,
+            aux_generator,
+            window_size,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<E: CurveAffine, N: FieldExt, const NUMBER_OF_LIMBS: usize, const BIT_LEN_LIMB: usize>
+    Circuit<N> for EcdsaBatchVerifyCircuit<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>
+where
+    P128Pow5T3: Spec<N, P128_WIDTH, P128_RATE>,
+{
+    type Config = EcdsaConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        let batch_size = self.signatures.len();
+        Self::new(
+            vec![Value::unknown(); batch_size],
+            vec![Value::unknown(); batch_size],
+            vec![Value::unknown(); batch_size],
+            self.aux_generator,
+            self.window_size,
+        )
+    }
+
+    fn configure(meta: &mut ConstraintSystem<N>) -> Self::Config {
+        let main_gate_config = MainGate::<N>::configure(meta);
+        EcdsaConfig::configure::<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>(meta, &main_gate_config)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<N>,
+    ) -> Result<(), Error> {
+        let chip = EcdsaSignatureChip::<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>::load_batch(
+            &config,
+            self.aux_generator,
+            self.window_size,
+            self.signatures.len(),
+            &mut layouter,
+        )?;
+        let poseidon_chip = PoseidonChip::new(config.main_gate_config.clone());
+
+        layouter.assign_region(
+            || "verify ecdsa batch",
+            |mut region| {
+                let offset = &mut 0;
+                let ctx = &mut RegionCtx::new(&mut region, offset);
+
+                let public_keys = self
+                    .public_keys
+                    .iter()
+                    .map(|public_key| chip.assign_public_key(ctx, *public_key))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let signatures = self
+                    .signatures
+                    .iter()
+                    .map(|signature| chip.assign_signature(ctx, *signature))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let msg_hashes = self
+                    .msg_hashes
+                    .iter()
+                    .map(|msg_hash| chip.assign_scalar(ctx, *msg_hash))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                chip.verify_batch(ctx, &poseidon_chip, &public_keys, &msg_hashes, &signatures)
+            },
+        )?;
+
+        config.load_tables(&mut layouter)
+    }
+}
+
+/// [`EcdsaConfig`] with an instance column, for circuits exposing public
+/// inputs.
+#[derive(Clone, Debug)]
+pub struct EcdsaInstanceConfig {
+    ecdsa_config: EcdsaConfig,
+    instance: Column<Instance>,
+}
+
+impl EcdsaInstanceConfig {
+    pub fn configure<
+        E: CurveAffine,
+        N: FieldExt,
+        const NUMBER_OF_LIMBS: usize,
+        const BIT_LEN_LIMB: usize,
+    >(
+        meta: &mut ConstraintSystem<N>,
+    ) -> Self {
+        let main_gate_config = MainGate::<N>::configure(meta);
+        let ecdsa_config =
+            EcdsaConfig::configure::<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>(meta, &main_gate_config);
+
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        EcdsaInstanceConfig {
+            ecdsa_config,
+            instance,
+        }
+    }
+
+    /// Constrains `cells` to the instance column, in order.
+    fn expose_public_inputs<N: FieldExt>(
+        &self,
+        layouter: &mut impl Layouter<N>,
+        cells: &[AssignedValue<N>],
+    ) -> Result<(), Error> {
+        for (row, cell) in cells.iter().enumerate() {
+            layouter.constrain_instance(cell.cell(), self.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+/// Proves that `signature` by `public_key` is a valid ECDSA signature over
+/// curve `E` of the SHA-256 digest of `message`, hashed in-circuit.
+///
+/// Messages of up to `max_message_len` bytes are accepted, and the instance
+/// column commits to the public key and the message, see
+/// [`EcdsaSha256VerifyCircuit::instance`]. Other parameters are those of
+/// [`EcdsaVerifyCircuit`].
+#[derive(Clone, Debug, Default)]
+pub struct EcdsaSha256VerifyCircuit<
+    E: CurveAffine,
+    N: FieldExt,
+    const NUMBER_OF_LIMBS: usize,
+    const BIT_LEN_LIMB: usize,
+> {
+    pub public_key: Value<E>,
+    pub signature: Value<Signature<E>>,
+    pub message: Value<Vec<u8>>,
+    pub max_message_len: usize,
+
+    pub aux_generator: E,
+    pub window_size: usize,
+    _marker: PhantomData<N>,
+}
+
+impl<E: CurveAffine, N: FieldExt, const NUMBER_OF_LIMBS: usize, const BIT_LEN_LIMB: usize>
+    EcdsaSha256VerifyCircuit<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>
+{
+    pub fn new(
+        public_key: Value<E>,
+        signature: Value<Signature<E>>,
+        message: Value<Vec<u8>>,
+        max_message_len: usize,
+        aux_generator: E,
+        window_size: usize,
+    ) -> Self {
+        Self {
+            public_key,
+            signature,
+            message,
+            max_message_len,
+            aux_generator,
+            window_size,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Values of the instance column, laid out as
+    /// `[public_key x limbs, public_key y limbs, message length, message bytes]`
+    /// with the message padded with zeros to `max_message_len` bytes.
+    pub fn instance(public_key: E, message: &[u8], max_message_len: usize) -> Vec<N> {
+        assert!(message.len() <= max_message_len);
+        let coordinates = public_key.coordinates().unwrap();
+
+        let mut instance = vec![];
+        for coordinate in [coordinates.x(), coordinates.y()] {
+            instance.extend(decompose_limbs_with::<E::Base, N>(
+                *coordinate,
+                NUMBER_OF_LIMBS,
+                BIT_LEN_LIMB,
+            ));
+        }
+        instance.push(N::from(message.len() as u64));
+        instance
+            .extend((0..max_message_len).map(|i| N::from(*message.get(i).unwrap_or(&0) as u64)));
+        instance
+    }
+}
+
+impl<E: CurveAffine, N: FieldExt, const NUMBER_OF_LIMBS: usize, const BIT_LEN_LIMB: usize>
+    Circuit<N> for EcdsaSha256VerifyCircuit<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>
+{
+    type Config = EcdsaInstanceConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(
+            Value::unknown(),
+            Value::unknown(),
+            Value::unknown(),
+            self.max_message_len,
+            self.aux_generator,
+            self.window_size,
+        )
+    }
+
+    fn configure(meta: &mut ConstraintSystem<N>) -> Self::Config {
+        EcdsaInstanceConfig::configure::<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<N>,
+    ) -> Result<(), Error> {
+        let chip = EcdsaSignatureChip::<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>::load(
+            &config.ecdsa_config,
+            self.aux_generator,
+            self.window_size,
+            &mut layouter,
+        )?;
+        let sha256_chip = Sha256Chip::new(config.ecdsa_config.main_gate_config.clone());
+
+        let public_inputs = layouter.assign_region(
+            || "verify ecdsa over sha256",
+            |mut region| {
+                let offset = &mut 0;
+                let ctx = &mut RegionCtx::new(&mut region, offset);
+
+                let public_key = chip.assign_public_key(ctx, self.public_key)?;
+                let signature = chip.assign_signature(ctx, self.signature)?;
+                let (message, len) = sha256_chip.assign_message(
+                    ctx,
+                    self.message.as_ref().map(|message| message.as_slice()),
+                    self.max_message_len,
+                )?;
+                let digest = sha256_chip.digest(ctx, &message, &len)?;
+                let msg_hash = chip.assign_digest(ctx, &digest)?;
+                chip.verify(ctx, &public_key, &msg_hash, &signature)?;
+
+                let mut public_inputs: Vec<AssignedValue<N>> = public_key
+                    .x()
+                    .limbs()
+                    .iter()
+                    .chain(public_key.y().limbs().iter())
+                    .map(|limb| limb.into())
+                    .collect();
+                public_inputs.push(len);
+                public_inputs.extend(message);
+                Ok(public_inputs)
+            },
+        )?;
+        config.expose_public_inputs(&mut layouter, &public_inputs)?;
+
+        config.ecdsa_config.load_tables(&mut layouter)
+    }
+}
+
+/// Proves that `signature` is a valid ECDSA signature over curve `E` of
+/// `msg_hash` by the key of an Ethereum address, without revealing the key.
+///
+/// The instance column commits to the address and the message hash, see
+/// [`EcdsaAddressVerifyCircuit::instance`]. Other parameters are those of
+/// [`EcdsaVerifyCircuit`].
+#[derive(Clone, Debug, Default)]
+pub struct EcdsaAddressVerifyCircuit<
+    E: CurveAffine,
+    N: FieldExt,
+    const NUMBER_OF_LIMBS: usize,
+    const BIT_LEN_LIMB: usize,
+> {
+    pub public_key: Value<E>,
+    pub signature: Value<Signature<E>>,
+    pub msg_hash: Value<E::Scalar>,
+
+    pub aux_generator: E,
+    pub window_size: usize,
+    _marker: PhantomData<N>,
+}
+
+impl<E: CurveAffine, N: FieldExt, const NUMBER_OF_LIMBS: usize, const BIT_LEN_LIMB: usize>
+    EcdsaAddressVerifyCircuit<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>
+{
+    pub fn new(
+        public_key: Value<E>,
+        signature: Value<Signature<E>>,
+        msg_hash: Value<E::Scalar>,
+        aux_generator: E,
+        window_size: usize,
+    ) -> Self {
+        Self {
+            public_key,
+            signature,
+            msg_hash,
+            aux_generator,
+            window_size,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Values of the instance column, laid out as
+    /// `[address bytes, msg_hash limbs]`.
+    pub fn instance(address: [u8; 20], msg_hash: E::Scalar) -> Vec<N> {
+        let mut instance: Vec<N> = address.iter().map(|byte| N::from(*byte as u64)).collect();
+        instance.extend(decompose_limbs_with::<E::Scalar, N>(
+            msg_hash,
+            NUMBER_OF_LIMBS,
+            BIT_LEN_LIMB,
+        ));
+        instance
+    }
+}
+
+impl<E: CurveAffine, N: FieldExt, const NUMBER_OF_LIMBS: usize, const BIT_LEN_LIMB: usize>
+    Circuit<N> for EcdsaAddressVerifyCircuit<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>
+{
+    type Config = EcdsaInstanceConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(
+            Value::unknown(),
+            Value::unknown(),
+            Value::unknown(),
+            self.aux_generator,
+            self.window_size,
+        )
+    }
+
+    fn configure(meta: &mut ConstraintSystem<N>) -> Self::Config {
+        EcdsaInstanceConfig::configure::<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<N>,
+    ) -> Result<(), Error> {
+        let chip = EcdsaSignatureChip::<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>::load(
+            &config.ecdsa_config,
+            self.aux_generator,
+            self.window_size,
+            &mut layouter,
+        )?;
+        let keccak_chip = KeccakChip::new(config.ecdsa_config.main_gate_config.clone());
+
+        let public_inputs = layouter.assign_region(
+            || "verify ecdsa by address",
+            |mut region| {
+                let offset = &mut 0;
+                let ctx = &mut RegionCtx::new(&mut region, offset);
+
+                let public_key = chip.assign_public_key(ctx, self.public_key)?;
+                let signature = chip.assign_signature(ctx, self.signature)?;
+                let msg_hash = chip.assign_scalar(ctx, self.msg_hash)?;
+                chip.verify(ctx, &public_key, &msg_hash, &signature)?;
+
+                let mut public_inputs = chip.ethereum_address(ctx, &keccak_chip, &public_key)?;
+                public_inputs.extend(msg_hash.limbs().iter().map(|limb| limb.into()));
+                Ok(public_inputs)
+            },
+        )?;
+        config.expose_public_inputs(&mut layouter, &public_inputs)?;
+
+        config.ecdsa_config.load_tables(&mut layouter)
+    }
+}
+
+/// Proves that the `ecrecover` precompile of the EVM returns the address of
+/// the instance column for `msg_hash`, `signature` and recovery id `v`, see
+/// [`recover`]. The recovered public key is a private witness.
+///
+/// The instance column is laid out as
+/// `[msg_hash limbs, v, r limbs, s limbs, address bytes]`, see
+/// [`EcrecoverCircuit::instance`]. Other parameters are those of
+/// [`EcdsaVerifyCircuit`].
+#[derive(Clone, Debug, Default)]
+pub struct EcrecoverCircuit<
+    E: CurveAffine,
+    N: FieldExt,
+    const NUMBER_OF_LIMBS: usize,
+    const BIT_LEN_LIMB: usize,
+> {
+    pub msg_hash: Value<E::Scalar>,
+    pub signature: Value<Signature<E>>,
+    pub v: Value<u8>,
+
+    pub aux_generator: E,
+    pub window_size: usize,
+    _marker: PhantomData<N>,
+}
+
+impl<E: CurveAffine, N: FieldExt, const NUMBER_OF_LIMBS: usize, const BIT_LEN_LIMB: usize>
+    EcrecoverCircuit<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>
+{
+    pub fn new(
+        msg_hash: Value<E::Scalar>,
+        signature: Value<Signature<E>>,
+        v: Value<u8>,
+        aux_generator: E,
+        window_size: usize,
+    ) -> Self {
+        Self {
+            msg_hash,
+            signature,
+            v,
+            aux_generator,
+            window_size,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Values of the instance column.
+    pub fn instance(
+        msg_hash: E::Scalar,
+        signature: Signature<E>,
+        v: u8,
+        address: [u8; 20],
+    ) -> Vec<N> {
+        let limbs =
+            |scalar| decompose_limbs_with::<E::Scalar, N>(scalar, NUMBER_OF_LIMBS, BIT_LEN_LIMB);
+
+        let mut instance = limbs(msg_hash);
+        instance.push(N::from(v as u64));
+        instance.extend(limbs(signature.0));
+        instance.extend(limbs(signature.1));
+        instance.extend(address.iter().map(|byte| N::from(*byte as u64)));
+        instance
+    }
+}
+
+impl<E: CurveAffine, N: FieldExt, const NUMBER_OF_LIMBS: usize, const BIT_LEN_LIMB: usize>
+    Circuit<N> for EcrecoverCircuit<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>
+{
+    type Config = EcdsaInstanceConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(
+            Value::unknown(),
+            Value::unknown(),
+            Value::unknown(),
+            self.aux_generator,
+            self.window_size,
+        )
+    }
+
+    fn configure(meta: &mut ConstraintSystem<N>) -> Self::Config {
+        EcdsaInstanceConfig::configure::<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<N>,
+    ) -> Result<(), Error> {
+        let chip = EcdsaSignatureChip::<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>::load(
+            &config.ecdsa_config,
+            self.aux_generator,
+            self.window_size,
+            &mut layouter,
+        )?;
+        let keccak_chip = KeccakChip::new(config.ecdsa_config.main_gate_config.clone());
+
+        let public_inputs = layouter.assign_region(
+            || "ecrecover",
+            |mut region| {
+                let offset = &mut 0;
+                let ctx = &mut RegionCtx::new(&mut region, offset);
+
+                let msg_hash = chip.assign_scalar(ctx, self.msg_hash)?;
+                let signature = chip.assign_signature(ctx, self.signature)?;
+                let v = chip
+                    .main_gate
+                    .assign_value(ctx, self.v.map(|v| N::from(v as u64)))?;
+                let public_key = chip.recover(ctx, &msg_hash, &signature, &v)?;
+                let address = chip.ethereum_address(ctx, &keccak_chip, &public_key)?;
+
+                let limbs =
+                    |integer: &AssignedInteger<E::Scalar, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>| {
+                        integer
+                            .limbs()
+                            .iter()
+                            .map(|limb| limb.into())
+                            .collect::<Vec<AssignedValue<N>>>()
+                    };
+                let mut public_inputs = limbs(&msg_hash);
+                public_inputs.push(v);
+                public_inputs.extend(limbs(&signature.r));
+                public_inputs.extend(limbs(&signature.s));
+                public_inputs.extend(address);
+                Ok(public_inputs)
+            },
+        )?;
+        config.expose_public_inputs(&mut layouter, &public_inputs)?;
+
+        config.ecdsa_config.load_tables(&mut layouter)
+    }
+}
+
+/// ECDSA over curve `E` as a receipt signature scheme, with the crate's limb
+/// layout of [`NUMBER_OF_LIMBS`] limbs of [`BIT_LEN_LIMB`] bits. The signed
+/// message is the receipt hash lifted into the scalar field of `E`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Ecdsa<E: CurveAffine> {
+    /// Auxiliary point of the scalar multiplications. Must have an unknown
+    /// discrete logarithm, e.g. be random.
+    pub aux_generator: E,
+    pub window_size: usize,
+    /// Whether only low `s` signatures are accepted, so that every receipt
+    /// has a single valid signature, see [`EcdsaSignatureChip::assert_strict`].
+    pub strict: bool,
+}
+
+impl<E: CurveAffine> Ecdsa<E> {
+    /// Scheme with a random auxiliary generator and `window_size`.
+    pub fn new(window_size: usize) -> Self {
+        Self {
+            aux_generator: random_aux_generator(),
+            window_size,
+            strict: false,
+        }
+    }
+}
+
+impl<E: CurveAffine, N: FieldExt> ReceiptSignatureScheme<N> for Ecdsa<E> {
+    type PublicKey = E;
+    type Signature = Signature<E>;
+
+    type Config = EcdsaConfig;
+    type Chip = EcdsaSignatureChip<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>;
+    type AssignedPublicKey = AssignedPoint<E::Base, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>;
+    type AssignedSignature = AssignedEcdsaSig<E::Scalar, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>;
+
+    fn configure(
+        meta: &mut ConstraintSystem<N>,
+        main_gate_config: &MainGateConfig,
+    ) -> Self::Config {
+        EcdsaConfig::configure::<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>(meta, main_gate_config)
+    }
+
+    fn load_chip(
+        &self,
+        config: &Self::Config,
+        layouter: &mut impl Layouter<N>,
+    ) -> Result<Self::Chip, Error> {
+        let mut chip =
+            EcdsaSignatureChip::load(config, self.aux_generator, self.window_size, layouter)?;
+        chip.strict = self.strict;
+        Ok(chip)
+    }
+
+    fn load_tables(config: &Self::Config, layouter: &mut impl Layouter<N>) -> Result<(), Error> {
+        config.load_tables(layouter)
+    }
+
+    fn assign_public_key(
+        chip: &Self::Chip,
+        ctx: &mut RegionCtx<'_, N>,
+        public_key: Value<E>,
+    ) -> Result<Self::AssignedPublicKey, Error> {
+        chip.assign_public_key(ctx, public_key)
+    }
+
+    fn assign_signature(
+        chip: &Self::Chip,
+        ctx: &mut RegionCtx<'_, N>,
+        signature: Value<Signature<E>>,
+    ) -> Result<Self::AssignedSignature, Error> {
+        chip.assign_signature(ctx, signature)
+    }
+
+    fn verify(
+        chip: &Self::Chip,
+        ctx: &mut RegionCtx<'_, N>,
+        public_key: &Self::AssignedPublicKey,
+        msg: &AssignedValue<N>,
+        signature: &Self::AssignedSignature,
+    ) -> Result<(), Error> {
+        // The signed message is `msg` lifted into the scalar field of `E`. Its
+        // native representation is bound to `msg` so that the signature can't
+        // be verified over a message other than the one derived from the
+        // receipt fields.
+        let msg_hash = msg.value().map(|msg| convert_field::<N, E::Scalar>(*msg));
+        let msg_hash = chip.assign_scalar(ctx, msg_hash)?;
+        chip.main_gate.assert_equal(ctx, msg_hash.native(), msg)?;
+
+        if chip.strict {
+            chip.assert_strict(ctx, signature)?;
+        }
+        chip.verify(ctx, public_key, &msg_hash, signature)
+    }
+
+    fn public_key_cells(public_key: &Self::AssignedPublicKey) -> Vec<AssignedValue<N>> {
+        public_key
+            .x()
+            .limbs()
+            .iter()
+            .chain(public_key.y().limbs().iter())
+            .map(|limb| limb.into())
+            .collect()
+    }
+
+    fn public_key_values(public_key: &E) -> Vec<N> {
+        let coordinates = public_key.coordinates().unwrap();
+
+        let mut values = vec![];
+        values.extend(decompose_limbs::<E::Base, N>(*coordinates.x()));
+        values.extend(decompose_limbs::<E::Base, N>(*coordinates.y()));
+        values
+    }
+
+    fn shape_parameters(&self) -> Vec<u64> {
+        let mut parameters = vec![self.window_size as u64];
+        // appended only when set, so that keys of non-strict circuits remain
+        if self.strict {
+            parameters.push(1);
+        }
+        parameters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2::{
+        arithmetic::{CurveAffine, Field, FieldExt},
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::{CircuitLayout, MockProver},
+        halo2curves::{
+            group::Curve, pasta::Fp as PastaFp, secp256k1::Secp256k1Affine,
+            secp256r1::Secp256r1Affine,
+        },
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use maingate::{MainGate, MainGateInstructions, RegionCtx};
+    use num_bigint::BigUint;
+    use rand_core::OsRng;
+
+    use super::{
+        big_to_fe, ecrecover, ethereum_address, fe_to_big, half_order, is_low_s, normalize_s,
+        random_aux_generator, recover, recovery_id, sha256_msg_hash, sign, verify,
+        AssignedEcdsaSig, AssignedPoint, EcdsaAddressVerifyCircuit, EcdsaBatchVerifyCircuit,
+        EcdsaConfig, EcdsaSha256VerifyCircuit, EcdsaSignatureChip, EcdsaVerifyCircuit,
+        EcrecoverCircuit, GeneralEccChip, Integer, IntegerInstructions, Range, Rc, Signature,
+        UnassignedInteger,
+    };
+    use crate::{poseidon::PoseidonChip, utilities::RowCount, BIT_LEN_LIMB, NUMBER_OF_LIMBS};
+
+    #[test]
+    fn test_ecdsa() {
+        fn run<C: CurveAffine, N: FieldExt>() {
+            let g = C::generator();
+            // keypair
+            let sk = <C as CurveAffine>::ScalarExt::random(OsRng);
+            let pk = (g * sk).to_affine();
+
+            // random msg_hash
+            let msg_hash = <C as CurveAffine>::ScalarExt::random(OsRng);
+
+            let (r, s) = sign::<C>(sk, msg_hash);
+            assert!(verify(pk, msg_hash, (r, s)));
+
+            // prove valid signature
+            let k = 20;
+            let ecdsa_circuit = EcdsaVerifyCircuit::<C, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>::new(
+                Value::known(pk),
+                Value::known((r, s)),
+                Value::known(msg_hash),
+                random_aux_generator(),
+                2,
+            );
+
+            // print circuit
+            use plotters::prelude::*;
+            let root = BitMapBackend::new("target/ecdsa.png", (3840, 2160)).into_drawing_area();
+            CircuitLayout::default()
+                .render(k, &ecdsa_circuit, &root)
+                .unwrap();
+
+            let prover = MockProver::run(k, &ecdsa_circuit, vec![vec![]]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+
+            // signature over another message
+            let circuit = EcdsaVerifyCircuit {
+                msg_hash: Value::known(msg_hash + <C as CurveAffine>::ScalarExt::one()),
+                ..ecdsa_circuit
+            };
+            let prover = MockProver::run(k, &circuit, vec![vec![]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+
+        run::<Secp256k1Affine, PastaFp>();
+        run::<Secp256r1Affine, PastaFp>();
+    }
+
+    fn from_hex<F: FieldExt>(hex: &str) -> F {
+        big_to_fe(&BigUint::parse_bytes(hex.as_bytes(), 16).unwrap())
+    }
+
+    #[test]
+    fn test_p256_vectors() {
+        type Scalar = <Secp256r1Affine as CurveAffine>::ScalarExt;
+
+        // RFC 6979, A.2.5
+        let sk: Scalar =
+            from_hex("c9afa9d845ba75166b5c215767b1d6934e50c3db36e89b127b8a622b120f6721");
+        let pk = Secp256r1Affine::from_xy(
+            from_hex("60fed4ba255a9d31c961eb74c6356d68c049b8923b61fa6ce669622e60f29fb6"),
+            from_hex("7903fe1008b8bc99a41ae9e95628bc64f2f1b20c2d7e9f5177a3c294d4462299"),
+        )
+        .unwrap();
+        assert_eq!((Secp256r1Affine::generator() * sk).to_affine(), pk);
+
+        for (message, r, s) in [
+            (
+                &b"sample"[..],
+                "efd48b2aacb6a8fd1140dd9cd45e81d69d2c877b56aaf991c34d0ea84eaf3716",
+                "f7cb1c942d657c41d436c7a1b6e29f65f3e900dbb9aff4064dc4ab2f843acda8",
+            ),
+            (
+                &b"test"[..],
+                "f1abb023518351cd71d881567b1ea663ed3efcf6c5132b354f28d3b0b7d38367",
+                "019f4113742a2b14bd25926b49c649155f267e60d3814b4c0cc84250e46f0083",
+            ),
+        ] {
+            let msg_hash = sha256_msg_hash::<Secp256r1Affine>(message);
+            let signature: (Scalar, Scalar) = (from_hex(r), from_hex(s));
+            assert!(verify(pk, msg_hash, signature));
+            assert!(!verify(pk, msg_hash + Scalar::one(), signature));
+
+            let k = 20;
+            let circuit =
+                EcdsaVerifyCircuit::<Secp256r1Affine, PastaFp, NUMBER_OF_LIMBS, BIT_LEN_LIMB>::new(
+                    Value::known(pk),
+                    Value::known(signature),
+                    Value::known(msg_hash),
+                    random_aux_generator(),
+                    2,
+                );
+            let prover = MockProver::run(k, &circuit, vec![vec![]]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_ecdsa_sha256() {
+        type Circuit =
+            EcdsaSha256VerifyCircuit<Secp256k1Affine, PastaFp, NUMBER_OF_LIMBS, BIT_LEN_LIMB>;
+
+        let sk = <Secp256k1Affine as CurveAffine>::ScalarExt::random(OsRng);
+        let pk = (Secp256k1Affine::generator() * sk).to_affine();
+        let message = b"receipt #1: 100 from account 1 to account 2";
+        let max_message_len = 48;
+        let signature = sign::<Secp256k1Affine>(sk, sha256_msg_hash::<Secp256k1Affine>(message));
+
+        let k = 20;
+        let circuit = Circuit::new(
+            Value::known(pk),
+            Value::known(signature),
+            Value::known(message.to_vec()),
+            max_message_len,
+            random_aux_generator(),
+            2,
+        );
+        let instance = Circuit::instance(pk, message, max_message_len);
+        let prover = MockProver::run(k, &circuit, vec![vec![], instance.clone()]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // public inputs committing to another message
+        let other = b"receipt #1: 900 from account 1 to account 2";
+        let other_instance = Circuit::instance(pk, other, max_message_len);
+        let prover = MockProver::run(k, &circuit, vec![vec![], other_instance]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // signature over another message
+        let circuit = Circuit {
+            message: Value::known(other.to_vec()),
+            ..circuit
+        };
+        let other_instance = Circuit::instance(pk, other, max_message_len);
+        let prover = MockProver::run(k, &circuit, vec![vec![], other_instance]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_ecdsa_address() {
+        type Circuit =
+            EcdsaAddressVerifyCircuit<Secp256k1Affine, PastaFp, NUMBER_OF_LIMBS, BIT_LEN_LIMB>;
+
+        // address of the secret key 1
+        assert_eq!(
+            hex::encode(ethereum_address(Secp256k1Affine::generator())),
+            "7e5f4552091a69125d5dfcb7b8c2659029395bdf"
+        );
+
+        let sk = <Secp256k1Affine as CurveAffine>::ScalarExt::random(OsRng);
+        let pk = (Secp256k1Affine::generator() * sk).to_affine();
+        let msg_hash = <Secp256k1Affine as CurveAffine>::ScalarExt::random(OsRng);
+        let signature = sign::<Secp256k1Affine>(sk, msg_hash);
+        let address = ethereum_address(pk);
+
+        let k = 20;
+        let circuit = Circuit::new(
+            Value::known(pk),
+            Value::known(signature),
+            Value::known(msg_hash),
+            random_aux_generator(),
+            2,
+        );
+        let instance = Circuit::instance(address, msg_hash);
+        let prover = MockProver::run(k, &circuit, vec![vec![], instance]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // another address
+        let other_pk = (Secp256k1Affine::generator()
+            * <Secp256k1Affine as CurveAffine>::ScalarExt::random(OsRng))
+        .to_affine();
+        let instance = Circuit::instance(ethereum_address(other_pk), msg_hash);
+        let prover = MockProver::run(k, &circuit, vec![vec![], instance]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_recover() {
+        let sk = <Secp256k1Affine as CurveAffine>::ScalarExt::random(OsRng);
+        let pk = (Secp256k1Affine::generator() * sk).to_affine();
+        let msg_hash = <Secp256k1Affine as CurveAffine>::ScalarExt::random(OsRng);
+        let signature = sign::<Secp256k1Affine>(sk, msg_hash);
+
+        // `R` has an `x` of `r` unless `x(kG) >= n`, which is negligible
+        let v = recovery_id(pk, msg_hash, signature).unwrap();
+        assert_eq!(recover::<Secp256k1Affine>(msg_hash, signature, v), Some(pk));
+        assert_eq!(
+            ecrecover::<Secp256k1Affine>(msg_hash, signature, v),
+            Some(ethereum_address(pk))
+        );
+
+        // the other parity recovers another key
+        assert_ne!(
+            recover::<Secp256k1Affine>(msg_hash, signature, 55 - v),
+            Some(pk)
+        );
+        for v in [0, 1, 29] {
+            assert_eq!(recover::<Secp256k1Affine>(msg_hash, signature, v), None);
+        }
+        let (r, _) = signature;
+        assert_eq!(
+            recover::<Secp256k1Affine>(msg_hash, (r, Field::zero()), v),
+            None
+        );
+    }
+
+    #[test]
+    fn test_ecrecover() {
+        type Circuit = EcrecoverCircuit<Secp256k1Affine, PastaFp, NUMBER_OF_LIMBS, BIT_LEN_LIMB>;
+
+        let sk = <Secp256k1Affine as CurveAffine>::ScalarExt::random(OsRng);
+        let pk = (Secp256k1Affine::generator() * sk).to_affine();
+        let msg_hash = <Secp256k1Affine as CurveAffine>::ScalarExt::random(OsRng);
+        let signature = sign::<Secp256k1Affine>(sk, msg_hash);
+        let v = recovery_id(pk, msg_hash, signature).unwrap();
+
+        let k = 20;
+        let circuit = Circuit::new(
+            Value::known(msg_hash),
+            Value::known(signature),
+            Value::known(v),
+            random_aux_generator(),
+            2,
+        );
+        let instance = Circuit::instance(msg_hash, signature, v, ethereum_address(pk));
+        let prover = MockProver::run(k, &circuit, vec![vec![], instance]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // the other recovery id recovers another address
+        let circuit = Circuit {
+            v: Value::known(55 - v),
+            ..circuit
+        };
+        let instance = Circuit::instance(msg_hash, signature, 55 - v, ethereum_address(pk));
+        let prover = MockProver::run(k, &circuit, vec![vec![], instance]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    fn random_signatures(
+        batch_size: usize,
+    ) -> Vec<(
+        Secp256k1Affine,
+        Signature<Secp256k1Affine>,
+        <Secp256k1Affine as CurveAffine>::ScalarExt,
+    )> {
+        (0..batch_size)
+            .map(|_| {
+                let sk = <Secp256k1Affine as CurveAffine>::ScalarExt::random(OsRng);
+                let pk = (Secp256k1Affine::generator() * sk).to_affine();
+                let msg_hash = <Secp256k1Affine as CurveAffine>::ScalarExt::random(OsRng);
+                (pk, sign::<Secp256k1Affine>(sk, msg_hash), msg_hash)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ecdsa_batch() {
+        type Circuit =
+            EcdsaBatchVerifyCircuit<Secp256k1Affine, PastaFp, NUMBER_OF_LIMBS, BIT_LEN_LIMB>;
+
+        let run = |batch: &[(
+            Secp256k1Affine,
+            Signature<Secp256k1Affine>,
+            <Secp256k1Affine as CurveAffine>::ScalarExt,
+        )]| {
+            let circuit = Circuit::new(
+                batch.iter().map(|(pk, _, _)| Value::known(*pk)).collect(),
+                batch.iter().map(|(_, sig, _)| Value::known(*sig)).collect(),
+                batch
+                    .iter()
+                    .map(|(_, _, msg_hash)| Value::known(*msg_hash))
+                    .collect(),
+                random_aux_generator(),
+                2,
+            );
+            MockProver::run(20, &circuit, vec![vec![]])
+                .unwrap()
+                .verify()
+        };
+
+        let mut batch = random_signatures(2);
+        assert_eq!(run(&batch), Ok(()));
+
+        // one signature over another message
+        batch[1].2 += <Secp256k1Affine as CurveAffine>::ScalarExt::one();
+        assert!(run(&batch).is_err());
+    }
+
+    /// Verifies signatures one by one or in a batch, recording the rows taken
+    /// by the verification.
+    #[derive(Debug)]
+    struct RowCountCircuit {
+        batch: Vec<(
+            Secp256k1Affine,
+            Signature<Secp256k1Affine>,
+            <Secp256k1Affine as CurveAffine>::ScalarExt,
+        )>,
+        batched: bool,
+        aux_generator: Secp256k1Affine,
+        rows: RowCount,
+    }
+
+    impl Circuit<PastaFp> for RowCountCircuit {
+        type Config = EcdsaConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                batch: self.batch.clone(),
+                batched: self.batched,
+                aux_generator: self.aux_generator,
+                rows: RowCount::default(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<PastaFp>) -> Self::Config {
+            let main_gate_config = MainGate::<PastaFp>::configure(meta);
+            EcdsaConfig::configure::<Secp256k1Affine, PastaFp, NUMBER_OF_LIMBS, BIT_LEN_LIMB>(
+                meta,
+                &main_gate_config,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<PastaFp>,
+        ) -> Result<(), Error> {
+            type Chip = EcdsaSignatureChip<Secp256k1Affine, PastaFp, NUMBER_OF_LIMBS, BIT_LEN_LIMB>;
+            let chip = Chip::load_batch(
+                &config,
+                self.aux_generator,
+                2,
+                self.batch.len(),
+                &mut layouter,
+            )?;
+            let poseidon_chip = PoseidonChip::new(config.main_gate_config().clone());
+
+            layouter.assign_region(
+                || "verify ecdsa",
+                |mut region| {
+                    let offset = &mut 0;
+                    let ctx = &mut RegionCtx::new(&mut region, offset);
+
+                    let mut public_keys = vec![];
+                    let mut signatures = vec![];
+                    let mut msg_hashes = vec![];
+                    for (pk, signature, msg_hash) in self.batch.iter() {
+                        public_keys.push(chip.assign_public_key(ctx, Value::known(*pk))?);
+                        signatures.push(chip.assign_signature(ctx, Value::known(*signature))?);
+                        msg_hashes.push(chip.assign_scalar(ctx, Value::known(*msg_hash))?);
+                    }
+
+                    self.rows.record(ctx, |ctx| {
+                        if self.batched {
+                            chip.verify_batch(
+                                ctx,
+                                &poseidon_chip,
+                                &public_keys,
+                                &msg_hashes,
+                                &signatures,
+                            )
+                        } else {
+                            for ((pk, msg_hash), signature) in public_keys
+                                .iter()
+                                .zip(msg_hashes.iter())
+                                .zip(signatures.iter())
+                            {
+                                chip.verify(ctx, pk, msg_hash, signature)?;
+                            }
+                            Ok(())
+                        }
+                    })
+                },
+            )?;
+
+            config.load_tables(&mut layouter)
+        }
+    }
+
+    #[test]
+    fn test_batch_row_count() {
+        let rows = |batch_size: usize, batched: bool| {
+            let circuit = RowCountCircuit {
+                batch: random_signatures(batch_size),
+                batched,
+                aux_generator: random_aux_generator(),
+                rows: RowCount::default(),
+            };
+            let prover = MockProver::run(21, &circuit, vec![vec![]]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+            circuit.rows.get()
+        };
+
+        // see the docs of `verify_batch`, independent verifications are all
+        // alike so they take `n` times the rows of one
+        let independent = rows(1, false);
+        assert_eq!(rows(2, false), 2 * independent);
+        let batched = [1, 2, 3, 4].map(|batch_size| rows(batch_size, true));
+        let slope = batched[1] - batched[0];
+        for (i, pair) in batched.windows(2).enumerate() {
+            assert_eq!(pair[1] - pair[0], slope, "step from {} signatures", i + 1);
+        }
+        assert!(slope < independent);
+        for (i, batch_rows) in batched.iter().enumerate().skip(2) {
+            assert!(*batch_rows < (i + 1) * independent);
+        }
+    }
+
+    #[test]
+    fn test_normalize_s() {
+        type Scalar = <Secp256k1Affine as CurveAffine>::ScalarExt;
+
+        let sk = Scalar::random(OsRng);
+        let pk = (Secp256k1Affine::generator() * sk).to_affine();
+        let msg_hash = Scalar::random(OsRng);
+        let (r, s) = sign::<Secp256k1Affine>(sk, msg_hash);
+
+        // both forms are valid and exactly one has a low `s`
+        assert!(verify(pk, msg_hash, (r, s)));
+        assert!(verify(pk, msg_hash, (r, -s)));
+        assert_ne!(
+            is_low_s::<Secp256k1Affine>((r, s)),
+            is_low_s::<Secp256k1Affine>((r, -s))
+        );
+        for signature in [(r, s), (r, -s)] {
+            let normalized = normalize_s::<Secp256k1Affine>(signature);
+            assert!(is_low_s::<Secp256k1Affine>(normalized));
+            assert!(normalized == (r, s) || normalized == (r, -s));
+        }
+    }
+
+    #[test]
+    fn test_ecdsa_strict() {
+        type Circuit = EcdsaVerifyCircuit<Secp256k1Affine, PastaFp, NUMBER_OF_LIMBS, BIT_LEN_LIMB>;
+
+        let sk = <Secp256k1Affine as CurveAffine>::ScalarExt::random(OsRng);
+        let pk = (Secp256k1Affine::generator() * sk).to_affine();
+        let msg_hash = <Secp256k1Affine as CurveAffine>::ScalarExt::random(OsRng);
+        let (r, s) = normalize_s::<Secp256k1Affine>(sign::<Secp256k1Affine>(sk, msg_hash));
+
+        let k = 20;
+        let circuit = Circuit {
+            strict: true,
+            ..Circuit::new(
+                Value::known(pk),
+                Value::known((r, s)),
+                Value::known(msg_hash),
+                random_aux_generator(),
+                2,
+            )
+        };
+        let prover = MockProver::run(k, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // the malleated signature is only rejected in strict mode
+        let malleated = Circuit {
+            signature: Value::known((r, -s)),
+            ..circuit.clone()
+        };
+        let prover = MockProver::run(k, &malleated, vec![vec![]]).unwrap();
+        assert!(prover.verify().is_err());
+        let malleated = Circuit {
+            strict: false,
+            ..malleated
+        };
+        let prover = MockProver::run(k, &malleated, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+    }
+
+    /// Constrains a signature of any integers `r` and `s` of 4 limbs of 68
+    /// bits to be strict.
+    #[derive(Debug, Default)]
+    struct StrictCircuit {
+        r: BigUint,
+        s: BigUint,
+    }
+
+    impl Circuit<PastaFp> for StrictCircuit {
+        type Config = EcdsaConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<PastaFp>) -> Self::Config {
+            let main_gate_config = MainGate::<PastaFp>::configure(meta);
+            EcdsaConfig::configure::<Secp256k1Affine, PastaFp, NUMBER_OF_LIMBS, BIT_LEN_LIMB>(
+                meta,
+                &main_gate_config,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<PastaFp>,
+        ) -> Result<(), Error> {
+            type Chip = EcdsaSignatureChip<Secp256k1Affine, PastaFp, NUMBER_OF_LIMBS, BIT_LEN_LIMB>;
+            let chip = Chip::load(&config, random_aux_generator(), 2, &mut layouter)?;
+            let main_gate = MainGate::<PastaFp>::new(config.main_gate_config().clone());
+
+            layouter.assign_region(
+                || "strict signature",
+                |mut region| {
+                    let offset = &mut 0;
+                    let ctx = &mut RegionCtx::new(&mut region, offset);
+
+                    let mut assign = |integer: &BigUint| {
+                        let limbs = (0..NUMBER_OF_LIMBS)
+                            .map(|i| {
+                                let limb = (integer >> (i * BIT_LEN_LIMB))
+                                    % (BigUint::from(1u64) << BIT_LEN_LIMB);
+                                main_gate.assign_value(ctx, Value::known(big_to_fe(&limb)))
+                            })
+                            .collect::<Result<Vec<_>, Error>>()?;
+                        chip.integer_from_limbs(ctx, &limbs)
+                    };
+                    let signature = AssignedEcdsaSig {
+                        r: assign(&self.r)?,
+                        s: assign(&self.s)?,
+                    };
+                    chip.assert_strict(ctx, &signature)
+                },
+            )?;
+
+            config.load_tables(&mut layouter)
+        }
+    }
+
+    #[test]
+    fn test_assert_strict() {
+        let half = half_order::<<Secp256k1Affine as CurveAffine>::ScalarExt>();
+        let n = &half * 2u64 + 1u64;
+        let one = BigUint::from(1u64);
+
+        let run = |r: &BigUint, s: &BigUint| {
+            let circuit = StrictCircuit {
+                r: r.clone(),
+                s: s.clone(),
+            };
+            MockProver::run(18, &circuit, vec![vec![]])
+                .unwrap()
+                .verify()
+        };
+
+        assert_eq!(run(&one, &half), Ok(()));
+        assert_eq!(run(&(&n - 1u64), &one), Ok(()));
+
+        // r = 0 or r >= n
+        assert!(run(&BigUint::from(0u64), &one).is_err());
+        assert!(run(&n, &one).is_err());
+        assert!(run(&(&n + 1u64), &one).is_err());
+        // s = 0 or s > n / 2
+        assert!(run(&one, &BigUint::from(0u64)).is_err());
+        assert!(run(&one, &(&half + 1u64)).is_err());
+        assert!(run(&one, &(&n - 1u64)).is_err());
+    }
+
+    /// Constrains the encoding of the point of coordinates `x` and `y`, given
+    /// as integers of 4 limbs of 68 bits that may not be reduced, to `bytes`.
+    #[derive(Debug, Default)]
+    struct PublicKeyBytesCircuit {
+        x: BigUint,
+        y: BigUint,
+        bytes: Vec<u8>,
+    }
+
+    impl Circuit<PastaFp> for PublicKeyBytesCircuit {
+        type Config = EcdsaConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<PastaFp>) -> Self::Config {
+            let main_gate_config = MainGate::<PastaFp>::configure(meta);
+            EcdsaConfig::configure::<Secp256k1Affine, PastaFp, NUMBER_OF_LIMBS, BIT_LEN_LIMB>(
+                meta,
+                &main_gate_config,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<PastaFp>,
+        ) -> Result<(), Error> {
+            type Chip = EcdsaSignatureChip<Secp256k1Affine, PastaFp, NUMBER_OF_LIMBS, BIT_LEN_LIMB>;
+            let chip = Chip::load(&config, random_aux_generator(), 2, &mut layouter)?;
+            let main_gate = MainGate::<PastaFp>::new(config.main_gate_config().clone());
+            let (rns_base, _) =
+                GeneralEccChip::<Secp256k1Affine, PastaFp, NUMBER_OF_LIMBS, BIT_LEN_LIMB>::rns();
+            let rns_base = Rc::new(rns_base);
+
+            layouter.assign_region(
+                || "public key bytes",
+                |mut region| {
+                    let offset = &mut 0;
+                    let ctx = &mut RegionCtx::new(&mut region, offset);
+
+                    let mut assign = |integer: &BigUint| {
+                        let limbs: Vec<PastaFp> = (0..NUMBER_OF_LIMBS)
+                            .map(|i| {
+                                big_to_fe(
+                                    &((integer >> (i * BIT_LEN_LIMB))
+                                        % (BigUint::from(1u64) << BIT_LEN_LIMB)),
+                                )
+                            })
+                            .collect();
+                        let integer =
+                            Integer::from_limbs(&limbs.try_into().unwrap(), rns_base.clone());
+                        chip.ecc_chip.base_field_chip().assign_integer(
+                            ctx,
+                            UnassignedInteger::from(Value::known(integer)),
+                            Range::Remainder,
+                        )
+                    };
+                    let public_key = AssignedPoint::new(assign(&self.x)?, assign(&self.y)?);
+
+                    let bytes = chip.public_key_bytes(ctx, &public_key)?;
+                    for (byte, expected) in bytes.iter().zip(self.bytes.iter()) {
+                        let expected =
+                            main_gate.assign_constant(ctx, PastaFp::from(*expected as u64))?;
+                        main_gate.assert_equal(ctx, byte, &expected)?;
+                    }
+                    Ok(())
+                },
+            )?;
+
+            config.load_tables(&mut layouter)
+        }
+    }
+
+    #[test]
+    fn test_public_key_bytes() {
+        type Base = <Secp256k1Affine as CurveAffine>::Base;
+
+        // point of small `x`, so that `x + p` also fits in 256 bits
+        let point = (1u64..)
+            .find_map(|x| {
+                let x = Base::from(x);
+                let y = Option::<Base>::from((x.square() * x + Secp256k1Affine::b()).sqrt())?;
+                Option::from(Secp256k1Affine::from_xy(x, y))
+            })
+            .unwrap();
+        let coordinates = point.coordinates().unwrap();
+        let x = fe_to_big(coordinates.x());
+        let y = fe_to_big(coordinates.y());
+        let p = fe_to_big(&-Base::one()) + 1u64;
+
+        let encoding = |x: &BigUint, y: &BigUint| {
+            let mut bytes = vec![];
+            for coordinate in [x, y] {
+                let coordinate = coordinate.to_bytes_be();
+                bytes.extend(vec![0; 32 - coordinate.len()]);
+                bytes.extend(coordinate);
+            }
+            bytes
+        };
+        let run = |x: &BigUint, bytes: Vec<u8>| {
+            let circuit = PublicKeyBytesCircuit {
+                x: x.clone(),
+                y: y.clone(),
+                bytes,
+            };
+            MockProver::run(18, &circuit, vec![vec![]])
+                .unwrap()
+                .verify()
+        };
+
+        assert_eq!(run(&x, encoding(&x, &y)), Ok(()));
+
+        // limbs of `x + p` only encode the point as those of `x` do
+        let non_canonical_x = &x + &p;
+        assert!(non_canonical_x.bits() <= 256);
+        assert_eq!(run(&non_canonical_x, encoding(&x, &y)), Ok(()));
+        assert!(run(&non_canonical_x, encoding(&non_canonical_x, &y)).is_err());
+    }
+}
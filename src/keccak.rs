@@ -0,0 +1,346 @@
+//! Keccak-256 over `MainGate`, as used by Ethereum.
+//!
+//! Lanes are assigned as 64 little-endian bits, so the rotations and the
+//! permutation of lanes are free and `θ`, `χ` and `ι` cost a few rows per
+//! bit. Inputs have a fixed number of bytes and the padding is constant.
+
+use halo2::{arithmetic::FieldExt, plonk::Error};
+use maingate::{
+    AssignedCondition, AssignedValue, MainGate, MainGateConfig, MainGateInstructions, RegionCtx,
+    Term,
+};
+use sha3::{Digest, Keccak256};
+
+const ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+/// Rotation of lane `x + 5 * y` in `ρ`.
+const ROTATION_OFFSETS: [usize; 25] = [
+    0, 1, 62, 28, 27, //
+    36, 44, 6, 55, 20, //
+    3, 10, 43, 25, 39, //
+    41, 45, 15, 21, 8, //
+    18, 2, 61, 56, 14,
+];
+
+/// Bytes absorbed per permutation.
+const RATE: usize = 136;
+/// Bytes of the digest.
+const DIGEST_LEN: usize = 32;
+
+/// Keccak-256 digest of `input`.
+pub fn keccak256(input: &[u8]) -> [u8; 32] {
+    Keccak256::digest(input).into()
+}
+
+/// Little-endian bits of a 64-bit lane.
+type Lane<F> = Vec<AssignedCondition<F>>;
+
+fn rotate_left<F: FieldExt>(lane: &Lane<F>, n: usize) -> Lane<F> {
+    (0..64).map(|i| lane[(i + 64 - n) % 64].clone()).collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct KeccakChip<F: FieldExt> {
+    main_gate: MainGate<F>,
+}
+
+impl<F: FieldExt> KeccakChip<F> {
+    pub fn new(main_gate_config: MainGateConfig) -> Self {
+        Self {
+            main_gate: MainGate::new(main_gate_config),
+        }
+    }
+
+    /// Bytes of the digest of the bytes `input`, which are constrained to 8
+    /// bits.
+    pub fn digest(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        input: &[AssignedValue<F>],
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let main_gate = &self.main_gate;
+        let zero = main_gate.assign_constant(ctx, F::zero())?;
+        let one = main_gate.assign_constant(ctx, F::one())?;
+        let constant_bits = |byte: u8| -> Vec<AssignedCondition<F>> {
+            (0..8)
+                .map(|i| {
+                    if (byte >> i) & 1 == 1 {
+                        one.clone()
+                    } else {
+                        zero.clone()
+                    }
+                })
+                .collect()
+        };
+
+        // pad10*1 with the domain bit of the original Keccak
+        let num_blocks = input.len() / RATE + 1;
+        let mut padding = vec![0u8; num_blocks * RATE - input.len()];
+        padding[0] |= 0x01;
+        *padding.last_mut().unwrap() |= 0x80;
+
+        let mut bytes = input
+            .iter()
+            .map(|byte| main_gate.to_bits(ctx, byte, 8))
+            .collect::<Result<Vec<_>, Error>>()?;
+        bytes.extend(padding.into_iter().map(constant_bits));
+
+        let mut state: Vec<Lane<F>> = vec![vec![zero.clone(); 64]; 25];
+        for (i, block) in bytes.chunks(RATE).enumerate() {
+            for (lane, bytes) in state.iter_mut().zip(block.chunks(8)) {
+                let bits = bytes.iter().flatten();
+                *lane = if i == 0 {
+                    bits.cloned().collect()
+                } else {
+                    lane.iter()
+                        .zip(bits)
+                        .map(|(a, b)| self.xor(ctx, a, b))
+                        .collect::<Result<Vec<_>, Error>>()?
+                };
+            }
+            state = self.permute(ctx, state)?;
+        }
+
+        state
+            .iter()
+            .flatten()
+            .take(8 * DIGEST_LEN)
+            .cloned()
+            .collect::<Vec<_>>()
+            .chunks(8)
+            .map(|bits| {
+                let terms: Vec<Term<F>> = bits
+                    .iter()
+                    .enumerate()
+                    .map(|(i, bit)| Term::Assigned(bit, F::from(1 << i)))
+                    .collect();
+                main_gate.compose(ctx, &terms, F::zero())
+            })
+            .collect()
+    }
+
+    fn xor(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedCondition<F>,
+        b: &AssignedCondition<F>,
+    ) -> Result<AssignedCondition<F>, Error> {
+        // a + b - 2ab
+        let ab = self.main_gate.mul(ctx, a, b)?;
+        self.main_gate.compose(
+            ctx,
+            &[
+                Term::Assigned(a, F::one()),
+                Term::Assigned(b, F::one()),
+                Term::Assigned(&ab, -F::from(2)),
+            ],
+            F::zero(),
+        )
+    }
+
+    fn xor_lanes(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &Lane<F>,
+        b: &Lane<F>,
+    ) -> Result<Lane<F>, Error> {
+        a.iter()
+            .zip(b.iter())
+            .map(|(a, b)| self.xor(ctx, a, b))
+            .collect()
+    }
+
+    /// Keccak-f[1600] applied to the lanes `state`, indexed by `x + 5 * y`.
+    fn permute(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        mut state: Vec<Lane<F>>,
+    ) -> Result<Vec<Lane<F>>, Error> {
+        let main_gate = &self.main_gate;
+
+        for round_constant in ROUND_CONSTANTS {
+            // θ
+            let mut parities = vec![];
+            for x in 0..5 {
+                let mut parity = state[x].clone();
+                for y in 1..5 {
+                    parity = self.xor_lanes(ctx, &parity, &state[x + 5 * y])?;
+                }
+                parities.push(parity);
+            }
+            for x in 0..5 {
+                let d = self.xor_lanes(
+                    ctx,
+                    &parities[(x + 4) % 5],
+                    &rotate_left(&parities[(x + 1) % 5], 1),
+                )?;
+                for y in 0..5 {
+                    state[x + 5 * y] = self.xor_lanes(ctx, &state[x + 5 * y], &d)?;
+                }
+            }
+
+            // ρ and π
+            let mut permuted = state.clone();
+            for x in 0..5 {
+                for y in 0..5 {
+                    permuted[y + 5 * ((2 * x + 3 * y) % 5)] =
+                        rotate_left(&state[x + 5 * y], ROTATION_OFFSETS[x + 5 * y]);
+                }
+            }
+
+            // χ, a ^ (!b & c)
+            for x in 0..5 {
+                for y in 0..5 {
+                    let [a, b, c] = [x, x + 1, x + 2].map(|x| &permuted[x % 5 + 5 * y]);
+                    state[x + 5 * y] = (0..64)
+                        .map(|i| {
+                            let bc = main_gate.mul(ctx, &b[i], &c[i])?;
+                            let not_b_and_c = main_gate.compose(
+                                ctx,
+                                &[
+                                    Term::Assigned(&c[i], F::one()),
+                                    Term::Assigned(&bc, -F::one()),
+                                ],
+                                F::zero(),
+                            )?;
+                            self.xor(ctx, &a[i], &not_b_and_c)
+                        })
+                        .collect::<Result<Vec<_>, Error>>()?;
+                }
+            }
+
+            // ι, flipping the bits of the first lane set in the round constant
+            for (i, bit) in state[0].iter_mut().enumerate() {
+                if (round_constant >> i) & 1 == 1 {
+                    *bit = main_gate.compose(ctx, &[Term::Assigned(bit, -F::one())], F::one())?;
+                }
+            }
+        }
+
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use halo2::{
+        arithmetic::FieldExt,
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        halo2curves::pasta::Fp as PastaFp,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use maingate::{MainGate, MainGateConfig, MainGateInstructions, RegionCtx};
+
+    use super::{keccak256, KeccakChip};
+
+    #[test]
+    fn test_keccak256() {
+        assert_eq!(
+            hex::encode(keccak256(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct KeccakCircuit<F: FieldExt> {
+        input: Vec<Value<u8>>,
+        digest: Value<[u8; 32]>,
+        _marker: PhantomData<F>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for KeccakCircuit<F> {
+        type Config = MainGateConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                input: vec![Value::unknown(); self.input.len()],
+                ..Default::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            MainGate::<F>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let main_gate = MainGate::<F>::new(config.clone());
+            let keccak_chip = KeccakChip::new(config);
+
+            layouter.assign_region(
+                || "keccak",
+                |mut region| {
+                    let offset = &mut 0;
+                    let ctx = &mut RegionCtx::new(&mut region, offset);
+
+                    let input = self
+                        .input
+                        .iter()
+                        .map(|byte| main_gate.assign_value(ctx, byte.map(|b| F::from(b as u64))))
+                        .collect::<Result<Vec<_>, Error>>()?;
+                    let digest = keccak_chip.digest(ctx, &input)?;
+                    for (i, byte) in digest.iter().enumerate() {
+                        let expected = self.digest.map(|digest| F::from(digest[i] as u64));
+                        let expected = main_gate.assign_value(ctx, expected)?;
+                        main_gate.assert_equal(ctx, byte, &expected)?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn test_keccak_chip() {
+        let run = |input: &[u8], digest: [u8; 32]| {
+            let circuit = KeccakCircuit::<PastaFp> {
+                input: input.iter().map(|byte| Value::known(*byte)).collect(),
+                digest: Value::known(digest),
+                _marker: PhantomData,
+            };
+            let prover = MockProver::run(20, &circuit, vec![vec![]]).unwrap();
+            prover.verify()
+        };
+
+        // one and two blocks
+        for input in [vec![0x42; 64], (0..=255).collect::<Vec<u8>>()] {
+            assert_eq!(run(&input, keccak256(&input)), Ok(()));
+        }
+
+        let mut digest = keccak256(&[0x42; 64]);
+        digest[0] ^= 0x80;
+        assert!(run(&[0x42; 64], digest).is_err());
+    }
+}
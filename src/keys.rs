@@ -0,0 +1,461 @@
+//! Serialization of the IPA parameters and keys of `ReceiptCircuit`.
+//!
+//! Every file starts with a header
+//!
+//! ```text
+//! | magic (4) | version (4) | kind (1) | k (4) | fingerprint (32) |
+//! ```
+//!
+//! where the fingerprint commits to the constraint system and shape
+//! parameters of the circuit the keys were generated for (all zeros for
+//! parameters). Loading fails if any of them doesn't match what the caller
+//! expects, so keys of a different circuit configuration are never used to
+//! create or verify proofs.
+
+use std::{
+    fmt,
+    io::{self, Read, Write},
+};
+
+use halo2::{
+    halo2curves::pasta::{EqAffine, Fp as PastaFp},
+    plonk::{Circuit, ConstraintSystem, ProvingKey, VerifyingKey},
+    poly::{commitment::Params, ipa::commitment::ParamsIPA},
+    SerdeFormat,
+};
+
+use crate::{signature::ReceiptSignatureScheme, ReceiptCircuit};
+
+const MAGIC: [u8; 4] = *b"H2OP";
+/// Bumped whenever the layout of the files changes.
+pub const FORMAT_VERSION: u32 = 1;
+
+const SERDE_FORMAT: SerdeFormat = SerdeFormat::Processed;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Params = 0,
+    VerifyingKey = 1,
+    ProvingKey = 2,
+}
+
+impl Kind {
+    fn from_u8(kind: u8) -> Option<Self> {
+        match kind {
+            0 => Some(Kind::Params),
+            1 => Some(Kind::VerifyingKey),
+            2 => Some(Kind::ProvingKey),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    BadMagic,
+    UnsupportedVersion(u32),
+    UnknownKind(u8),
+    KindMismatch {
+        expected: Kind,
+        found: Kind,
+    },
+    KMismatch {
+        expected: u32,
+        found: u32,
+    },
+    /// The key was generated for a different circuit configuration.
+    FingerprintMismatch,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "io error: {}", e),
+            Error::BadMagic => write!(f, "not a halo2-ops key file"),
+            Error::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported format version {}, expected {}",
+                version, FORMAT_VERSION
+            ),
+            Error::UnknownKind(kind) => write!(f, "unknown key kind {}", kind),
+            Error::KindMismatch { expected, found } => {
+                write!(f, "expected {:?}, found {:?}", expected, found)
+            }
+            Error::KMismatch { expected, found } => {
+                write!(f, "expected k = {}, found k = {}", expected, found)
+            }
+            Error::FingerprintMismatch => {
+                write!(f, "key was generated for a different circuit configuration")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Header {
+    kind: Kind,
+    k: u32,
+    fingerprint: [u8; 32],
+}
+
+impl Header {
+    fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        writer.write_all(&[self.kind as u8])?;
+        writer.write_all(&self.k.to_le_bytes())?;
+        writer.write_all(&self.fingerprint)
+    }
+
+    fn read<R: Read>(reader: &mut R) -> Result<Self, Error> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(Error::BadMagic);
+        }
+
+        let mut version = [0u8; 4];
+        reader.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != FORMAT_VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
+        let mut kind = [0u8; 1];
+        reader.read_exact(&mut kind)?;
+        let kind = Kind::from_u8(kind[0]).ok_or(Error::UnknownKind(kind[0]))?;
+
+        let mut k = [0u8; 4];
+        reader.read_exact(&mut k)?;
+        let k = u32::from_le_bytes(k);
+
+        let mut fingerprint = [0u8; 32];
+        reader.read_exact(&mut fingerprint)?;
+
+        Ok(Self {
+            kind,
+            k,
+            fingerprint,
+        })
+    }
+
+    /// Reads a header and checks that it matches `expected`.
+    fn read_expected<R: Read>(reader: &mut R, expected: Header) -> Result<(), Error> {
+        let header = Self::read(reader)?;
+        if header.kind != expected.kind {
+            return Err(Error::KindMismatch {
+                expected: expected.kind,
+                found: header.kind,
+            });
+        }
+        if header.k != expected.k {
+            return Err(Error::KMismatch {
+                expected: expected.k,
+                found: header.k,
+            });
+        }
+        if header.fingerprint != expected.fingerprint {
+            return Err(Error::FingerprintMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Commits to the constraint system and shape parameters of `circuit` at
+/// `2^k` rows.
+pub fn circuit_fingerprint<S: ReceiptSignatureScheme<PastaFp>>(
+    k: u32,
+    circuit: &ReceiptCircuit<S, PastaFp>,
+) -> [u8; 32] {
+    let mut cs = ConstraintSystem::<PastaFp>::default();
+    ReceiptCircuit::<S, PastaFp>::configure(&mut cs);
+
+    let mut hasher = blake2b_simd::Params::new()
+        .hash_length(32)
+        .personal(b"halo2-ops-shape\0")
+        .to_state();
+    hasher.update(&k.to_le_bytes());
+    hasher.update(format!("{:?}", cs.pinned()).as_bytes());
+    for parameter in circuit.shape_parameters() {
+        hasher.update(&parameter.to_le_bytes());
+    }
+    hasher.finalize().as_bytes().try_into().unwrap()
+}
+
+pub fn write_params<W: Write>(writer: &mut W, params: &ParamsIPA<EqAffine>) -> io::Result<()> {
+    Header {
+        kind: Kind::Params,
+        k: params.k(),
+        fingerprint: [0; 32],
+    }
+    .write(writer)?;
+    params.write(writer)
+}
+
+/// Reads parameters for circuits of `2^k` rows.
+pub fn read_params<R: Read>(reader: &mut R, k: u32) -> Result<ParamsIPA<EqAffine>, Error> {
+    Header::read_expected(
+        reader,
+        Header {
+            kind: Kind::Params,
+            k,
+            fingerprint: [0; 32],
+        },
+    )?;
+    Ok(ParamsIPA::read(reader)?)
+}
+
+/// Writes `vk` generated for `circuit`.
+pub fn write_vk<W: Write, S: ReceiptSignatureScheme<PastaFp>>(
+    writer: &mut W,
+    vk: &VerifyingKey<EqAffine>,
+    k: u32,
+    circuit: &ReceiptCircuit<S, PastaFp>,
+) -> io::Result<()> {
+    Header {
+        kind: Kind::VerifyingKey,
+        k,
+        fingerprint: circuit_fingerprint(k, circuit),
+    }
+    .write(writer)?;
+    vk.write(writer, SERDE_FORMAT)
+}
+
+/// Reads a verifying key, failing unless it was generated for `circuit` at
+/// `2^k` rows.
+pub fn read_vk<R: Read, S: ReceiptSignatureScheme<PastaFp>>(
+    reader: &mut R,
+    k: u32,
+    circuit: &ReceiptCircuit<S, PastaFp>,
+) -> Result<VerifyingKey<EqAffine>, Error> {
+    Header::read_expected(
+        reader,
+        Header {
+            kind: Kind::VerifyingKey,
+            k,
+            fingerprint: circuit_fingerprint(k, circuit),
+        },
+    )?;
+    Ok(VerifyingKey::read::<_, ReceiptCircuit<S, PastaFp>>(
+        reader,
+        SERDE_FORMAT,
+    )?)
+}
+
+/// Writes `pk` generated for `circuit`.
+pub fn write_pk<W: Write, S: ReceiptSignatureScheme<PastaFp>>(
+    writer: &mut W,
+    pk: &ProvingKey<EqAffine>,
+    k: u32,
+    circuit: &ReceiptCircuit<S, PastaFp>,
+) -> io::Result<()> {
+    Header {
+        kind: Kind::ProvingKey,
+        k,
+        fingerprint: circuit_fingerprint(k, circuit),
+    }
+    .write(writer)?;
+    pk.write(writer, SERDE_FORMAT)
+}
+
+/// Reads a proving key, failing unless it was generated for `circuit` at
+/// `2^k` rows.
+pub fn read_pk<R: Read, S: ReceiptSignatureScheme<PastaFp>>(
+    reader: &mut R,
+    k: u32,
+    circuit: &ReceiptCircuit<S, PastaFp>,
+) -> Result<ProvingKey<EqAffine>, Error> {
+    Header::read_expected(
+        reader,
+        Header {
+            kind: Kind::ProvingKey,
+            k,
+            fingerprint: circuit_fingerprint(k, circuit),
+        },
+    )?;
+    Ok(ProvingKey::read::<_, ReceiptCircuit<S, PastaFp>>(
+        reader,
+        SERDE_FORMAT,
+    )?)
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2::{
+        circuit::Value,
+        halo2curves::{pasta::Fp as PastaFp, secp256k1::Secp256k1Affine},
+        poly::commitment::Params,
+    };
+
+    use super::{
+        circuit_fingerprint, read_params, read_pk, read_vk, write_params, write_pk, write_vk,
+        Error, Header, Kind,
+    };
+    use crate::{
+        prover::{keygen, setup, RECEIPT_K},
+        schnorr::Schnorr,
+        Ecdsa, ReceiptCircuit, SignatureMode, DEFAULT_AMOUNT_NUM_BITS, DEFAULT_REGISTRY_DEPTH,
+        DEFAULT_TIMESTAMP_NUM_BITS, DEFAULT_WINDOW_SIZE,
+    };
+
+    fn circuit() -> ReceiptCircuit<Ecdsa<Secp256k1Affine>, PastaFp> {
+        ReceiptCircuit {
+            b_registry_path: vec![Value::unknown(); DEFAULT_REGISTRY_DEPTH],
+            timestamp_num_bits: DEFAULT_TIMESTAMP_NUM_BITS,
+            amount_num_bits: DEFAULT_AMOUNT_NUM_BITS,
+            scheme: Ecdsa::new(DEFAULT_WINDOW_SIZE),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_params_roundtrip() {
+        let params = setup(4);
+        let mut bytes = vec![];
+        write_params(&mut bytes, &params).unwrap();
+
+        let read = read_params(&mut bytes.as_slice(), 4).unwrap();
+        assert_eq!(read.k(), 4);
+        assert!(matches!(
+            read_params(&mut bytes.as_slice(), 5),
+            Err(Error::KMismatch {
+                expected: 5,
+                found: 4
+            })
+        ));
+
+        let mut bad_version = bytes.clone();
+        bad_version[4] = 0xff;
+        assert!(matches!(
+            read_params(&mut bad_version.as_slice(), 4),
+            Err(Error::UnsupportedVersion(_))
+        ));
+
+        let mut bad_magic = bytes;
+        bad_magic[0] = 0;
+        assert!(matches!(
+            read_params(&mut bad_magic.as_slice(), 4),
+            Err(Error::BadMagic)
+        ));
+    }
+
+    #[test]
+    fn test_circuit_fingerprint() {
+        let circuit = circuit();
+        let fingerprint = circuit_fingerprint(RECEIPT_K, &circuit);
+        assert_eq!(fingerprint, circuit_fingerprint(RECEIPT_K, &circuit));
+        assert_ne!(fingerprint, circuit_fingerprint(RECEIPT_K + 1, &circuit));
+
+        // headers of keys saved for `circuit`, the keys themselves aren't
+        // read once the header is rejected
+        let header = |kind| {
+            let mut bytes = vec![];
+            Header {
+                kind,
+                k: RECEIPT_K,
+                fingerprint,
+            }
+            .write(&mut bytes)
+            .unwrap();
+            bytes
+        };
+        let vk_bytes = header(Kind::VerifyingKey);
+        let pk_bytes = header(Kind::ProvingKey);
+
+        for other in [
+            ReceiptCircuit {
+                b_registry_path: vec![Value::unknown(); DEFAULT_REGISTRY_DEPTH + 1],
+                ..circuit.clone()
+            },
+            ReceiptCircuit {
+                amount_num_bits: 128,
+                ..circuit.clone()
+            },
+            ReceiptCircuit {
+                timestamp_num_bits: 40,
+                ..circuit.clone()
+            },
+            ReceiptCircuit {
+                expose_expires_by: true,
+                ..circuit.clone()
+            },
+            ReceiptCircuit {
+                scheme: Ecdsa::new(DEFAULT_WINDOW_SIZE + 1),
+                ..circuit.clone()
+            },
+            ReceiptCircuit {
+                signature_mode: SignatureMode::CoSigned,
+                ..circuit.clone()
+            },
+        ] {
+            assert_ne!(fingerprint, circuit_fingerprint(RECEIPT_K, &other));
+            assert!(matches!(
+                read_vk(&mut vk_bytes.as_slice(), RECEIPT_K, &other),
+                Err(Error::FingerprintMismatch)
+            ));
+            assert!(matches!(
+                read_pk(&mut pk_bytes.as_slice(), RECEIPT_K, &other),
+                Err(Error::FingerprintMismatch)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_keys_roundtrip() {
+        // a Schnorr receipt fits in few rows, so keygen is cheap
+        let k = 15;
+        let circuit = ReceiptCircuit::<Schnorr, PastaFp> {
+            b_registry_path: vec![Value::unknown(); 8],
+            timestamp_num_bits: DEFAULT_TIMESTAMP_NUM_BITS,
+            amount_num_bits: DEFAULT_AMOUNT_NUM_BITS,
+            ..Default::default()
+        };
+        let params = setup(k);
+        let pk = keygen(&params, &circuit).unwrap();
+
+        let mut vk_bytes = vec![];
+        write_vk(&mut vk_bytes, pk.get_vk(), k, &circuit).unwrap();
+        let vk = read_vk(&mut vk_bytes.as_slice(), k, &circuit).unwrap();
+        assert_eq!(vk.transcript_repr(), pk.get_vk().transcript_repr());
+
+        let mut pk_bytes = vec![];
+        write_pk(&mut pk_bytes, &pk, k, &circuit).unwrap();
+        let read = read_pk(&mut pk_bytes.as_slice(), k, &circuit).unwrap();
+        assert_eq!(
+            read.get_vk().transcript_repr(),
+            pk.get_vk().transcript_repr()
+        );
+
+        // keys of a different configuration are rejected
+        let other = ReceiptCircuit {
+            amount_num_bits: 128,
+            ..circuit.clone()
+        };
+        assert!(matches!(
+            read_vk(&mut vk_bytes.as_slice(), k, &other),
+            Err(Error::FingerprintMismatch)
+        ));
+        assert!(matches!(
+            read_pk(&mut pk_bytes.as_slice(), k, &other),
+            Err(Error::FingerprintMismatch)
+        ));
+        assert!(matches!(
+            read_vk(&mut vk_bytes.as_slice(), k + 1, &circuit),
+            Err(Error::KMismatch { .. })
+        ));
+        // a verifying key isn't a proving key
+        assert!(matches!(
+            read_pk(&mut vk_bytes.as_slice(), k, &circuit),
+            Err(Error::KindMismatch { .. })
+        ));
+    }
+}
@@ -5,208 +5,421 @@
 //     amount,
 //     expires_by
 // }
-// 2. Hash => {a_index + b_index + amount + expires_by}
+// 2. Hash => Poseidon(Domain::Receipt, a_index, b_index, amount, expires_by), see `Receipt::hash`
 // 3. Take `b_public_key`, `a_index`, `b_index`, `amount` as public input to circuit and apply equality constraint
+// 4. Take `now` as public input and constrain `now < expires_by`
+// 5. Constrain `amount` to `amount_num_bits` bits so that it can't wrap around the field modulus
+// 6. Nullifier => Poseidon(Domain::Nullifier, nullifier_secret, Hash), see `Receipt::nullifier`
+// 7. Leaf => Poseidon(b_index, Poseidon(nullifier_secret), b_public_key) in registry, see `merkle`
+//    Every Poseidon message starts with the tag of its use, see `poseidon::Domain`
+// 8. A receipt supersedes an older one of the same channel iff its amount is greater, see `channel`
+// 9. N receipts to the same payee can be settled in one proof exposing their total, see `batch`
+// 10. Receipts of our own clients can instead be signed with Schnorr over Pallas, see `schnorr`
+// 11. `ReceiptCircuit` is generic over the signature scheme, see `signature::ReceiptSignatureScheme`
+// 12. Receipts can be co-signed by the payer `a` too, exposing both public keys, see `SignatureMode`
+// 13. Amounts can be hidden in a Pedersen commitment over Pallas, see `pedersen`
+// 14. Standalone ECDSA verification of any limb layout is `ecdsa::EcdsaVerifyCircuit`
+// 15. `ecdsa::EcdsaSha256VerifyCircuit` hashes the signed message in-circuit, see `sha256`
+// 16. `ecdsa::EcdsaAddressVerifyCircuit` exposes the Ethereum address of the signer, see `keccak`
+// 17. `ecdsa::EcrecoverCircuit` recovers the signer as the EVM `ecrecover` precompile does
+// 18. `ecdsa::EcdsaBatchVerifyCircuit` verifies signatures in one multi-scalar multiplication
+// 19. ECDSA also works over P-256 (`a = -3`) for passkey signatures, see `webauthn`
+// 20. `Ecdsa::strict` only accepts low `s` signatures, so that a receipt has one signature
+
+pub mod batch;
+pub mod channel;
+pub mod comparison;
+pub mod ecdsa;
+pub mod keccak;
+pub mod keys;
+pub mod merkle;
+pub mod pedersen;
+pub mod poseidon;
+pub mod prover;
+mod public_inputs;
+pub mod range_check;
+mod receipt;
+pub mod schnorr;
+pub mod sha256;
+pub mod signature;
+mod utilities;
+pub mod webauthn;
+
+pub use public_inputs::{decompose_limbs, ReceiptPublicInputs};
+pub use receipt::{
+    Receipt, DEFAULT_AMOUNT_NUM_BITS, DEFAULT_REGISTRY_DEPTH, DEFAULT_TIMESTAMP_NUM_BITS,
+    DEFAULT_WINDOW_SIZE,
+};
 
-use std::marker::PhantomData;
+pub use ecdsa::{Ecdsa, EcdsaVerifyCircuit};
+pub use signature::ReceiptSignatureScheme;
 
+use comparison::LessThanChip;
+use ff::{PrimeField, PrimeFieldBits};
 use halo2::{
-    arithmetic::{CurveAffine, Field, FieldExt},
-    circuit::{SimpleFloorPlanner, Value},
-    dev::{CircuitLayout, MockProver},
-    halo2curves::{
-        group::{Curve, Group},
-        pasta::Fp as PastaFp,
-        secp256k1::Secp256k1Affine,
-    },
-    plonk::Circuit,
+    arithmetic::FieldExt,
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
 };
-use maingate::{MainGate, MainGateConfig, RangeChip, RangeConfig};
+use maingate::{AssignedValue, MainGate, MainGateConfig, MainGateInstructions, RegionCtx};
+use merkle::MerkleChip;
+use num_bigint::BigUint;
+use num_traits::Num;
+use poseidon::{Domain, P128Pow5T3, PoseidonChip, Spec, P128_RATE, P128_WIDTH};
+use range_check::RangeCheckConfig;
+
+pub const BIT_LEN_LIMB: usize = 68;
+pub const NUMBER_OF_LIMBS: usize = 4;
+
+/// Running sum range checks decompose into single bits which keeps the
+/// degree of the window gate at 3.
+const RANGE_CHECK_WINDOW_NUM_BITS: usize = 1;
+
+/// Converts an element of field `F` into an element of field `T`, reducing
+/// it modulo the modulus of `T` if required.
+pub fn convert_field<F: FieldExt, T: FieldExt>(x: F) -> T {
+    let x = BigUint::from_bytes_le(x.to_repr().as_ref());
+    let modulus = BigUint::from_str_radix(&T::MODULUS[2..], 16).unwrap();
+    let x = x % modulus;
+    T::from_str_vartime(&x.to_str_radix(10)[..]).unwrap()
+}
 
 #[derive(Clone, Debug)]
-struct ReceiptConfig {
+pub struct ReceiptConfig<N: FieldExt + PrimeFieldBits, S: ReceiptSignatureScheme<N>> {
     main_gate_config: MainGateConfig,
-    range_check_config: RangeConfig,
+    signature_config: S::Config,
+    running_sum_config: RangeCheckConfig<N, RANGE_CHECK_WINDOW_NUM_BITS>,
+    instance: Column<Instance>,
+}
+
+impl<N: FieldExt + PrimeFieldBits, S: ReceiptSignatureScheme<N>> ReceiptConfig<N, S> {
+    /// Configures the columns and gates for verifying signatures of scheme
+    /// `S` and for range checks and comparisons of native values.
+    fn configure(meta: &mut ConstraintSystem<N>) -> Self {
+        let main_gate_config = MainGate::<N>::configure(meta);
+        let signature_config = S::configure(meta, &main_gate_config);
+
+        let z = meta.advice_column();
+        let constants = meta.fixed_column();
+        let running_sum_config = RangeCheckConfig::configure(meta, z, constants);
+
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+
+        ReceiptConfig {
+            main_gate_config,
+            signature_config,
+            running_sum_config,
+            instance,
+        }
+    }
+
+    /// Constrains `cells` to the receipt instance column, in order.
+    fn expose_public_inputs(
+        &self,
+        layouter: &mut impl Layouter<N>,
+        cells: &[&AssignedValue<N>],
+    ) -> Result<(), Error> {
+        for (row, cell) in cells.iter().enumerate() {
+            layouter.constrain_instance(cell.cell(), self.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+/// Signatures a receipt must carry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureMode {
+    /// Only the payee `b` signs, the default.
+    PayeeOnly,
+    /// Both the payer `a` and the payee `b` sign the same receipt hash, e.g.
+    /// to settle disputes.
+    CoSigned,
+}
+
+impl Default for SignatureMode {
+    fn default() -> Self {
+        SignatureMode::PayeeOnly
+    }
 }
 
+/// Cells of the receipt region used by the rest of the circuit.
+struct AssignedReceipt<N: FieldExt> {
+    b_public_key: Vec<AssignedValue<N>>,
+    /// Only assigned for co-signed receipts.
+    a_public_key: Option<Vec<AssignedValue<N>>>,
+    a_index: AssignedValue<N>,
+    b_index: AssignedValue<N>,
+    amount: AssignedValue<N>,
+    expires_by: AssignedValue<N>,
+    now: AssignedValue<N>,
+    nullifier: AssignedValue<N>,
+    registry_root: AssignedValue<N>,
+}
+
+impl<N: FieldExt> AssignedReceipt<N> {
+    /// Cells of the instance column in the order of
+    /// [`ReceiptPublicInputs::to_instance`], with `amount` in place of the
+    /// amount cell.
+    fn public_inputs<'a>(
+        &'a self,
+        amount: &[&'a AssignedValue<N>],
+        expose_expires_by: bool,
+    ) -> Vec<&'a AssignedValue<N>> {
+        let mut public_inputs: Vec<&AssignedValue<N>> = self.b_public_key.iter().collect();
+        public_inputs.extend([&self.a_index, &self.b_index]);
+        public_inputs.extend(amount);
+        public_inputs.extend([&self.now, &self.nullifier, &self.registry_root]);
+        if expose_expires_by {
+            public_inputs.push(&self.expires_by);
+        }
+        if let Some(a_public_key) = &self.a_public_key {
+            public_inputs.extend(a_public_key);
+        }
+        public_inputs
+    }
+}
+
+/// Proves that `b_signature` is a valid signature by `b_public_key` over the
+/// receipt `{a_index, b_index, amount, expires_by}`, and that the receipt
+/// hasn't expired at time `now`.
+///
+/// The signature scheme is `S`, e.g. [`Ecdsa`] over secp256k1 or
+/// [`schnorr::Schnorr`] over Pallas, and `scheme` holds its layout
+/// parameters.
+///
+/// The public nullifier is a PRF of the receipt hash keyed by
+/// `nullifier_secret`, so the same receipt always yields the same nullifier
+/// while the nullifier alone reveals nothing about the receipt. The secret is
+/// fixed per redeemer by the commitment to `nullifier_secret` in its
+/// registry leaf, so the spent-set of nullifiers prevents double redemption.
+///
+/// `b_public_key` must be registered at `b_index` in the public registry,
+/// i.e. `b_registry_path` must be the Merkle path of the leaf committing to
+/// `b_index`, `nullifier_secret` and `b_public_key` at position `b_index`
+/// (see [`merkle`]).
+/// The depth of the registry is the length of the path, and `b_index` is
+/// constrained to that many bits.
+///
+/// If `signature_mode` is [`SignatureMode::CoSigned`], `a_signature` must
+/// also be a valid signature by `a_public_key` over the same receipt hash.
+/// `a_public_key` isn't checked against the registry, verifiers compare the
+/// exposed key with the one registered at `a_index`.
+///
+/// `now` and `expires_by` are constrained to `timestamp_num_bits` bits and
+/// `amount` to `amount_num_bits` bits (e.g. 64 for u64 amounts). Both must be
+/// multiples of `RANGE_CHECK_WINDOW_NUM_BITS`.
+///
+/// Public inputs are described by [`ReceiptPublicInputs`]. `expires_by` is
+/// only exposed if `expose_expires_by` is set, and `a_public_key` only for
+/// co-signed receipts.
 #[derive(Clone, Debug, Default)]
-struct ReceiptCircuit<E: CurveAffine, N: FieldExt> {
-    a_index: Value<E>,
-    b_index: Value<E>,
-    amount: Value<E>,
-    expires_by: Value<E>,
+pub struct ReceiptCircuit<S: ReceiptSignatureScheme<N>, N: FieldExt> {
+    pub a_index: Value<N>,
+    pub b_index: Value<N>,
+    pub amount: Value<N>,
+    pub expires_by: Value<N>,
+
+    pub b_public_key: Value<S::PublicKey>,
+    pub b_signature: Value<S::Signature>,
+    /// Siblings on the registry path of `b_public_key`, from the bottom up.
+    pub b_registry_path: Vec<Value<N>>,
+
+    /// Only used for co-signed receipts.
+    pub a_public_key: Value<S::PublicKey>,
+    pub a_signature: Value<S::Signature>,
+
+    pub now: Value<N>,
+    pub nullifier_secret: Value<N>,
+
+    pub expose_expires_by: bool,
+    pub signature_mode: SignatureMode,
+    pub timestamp_num_bits: usize,
+    pub amount_num_bits: usize,
+
+    pub scheme: S,
+}
 
-    b_public_key: Value<E>,
-    b_signature: Value<(E, E)>,
+impl<S: ReceiptSignatureScheme<N>, N: FieldExt> ReceiptCircuit<S, N> {
+    /// Parameters that change the layout of the circuit, and hence its keys,
+    /// without changing its constraint system.
+    pub fn shape_parameters(&self) -> Vec<u64> {
+        let mut parameters = vec![
+            self.expose_expires_by as u64,
+            self.timestamp_num_bits as u64,
+            self.amount_num_bits as u64,
+            self.b_registry_path.len() as u64,
+            (self.signature_mode == SignatureMode::CoSigned) as u64,
+        ];
+        parameters.extend(self.scheme.shape_parameters());
+        parameters
+    }
+}
 
-    _marker: PhantomData<N>,
+impl<S: ReceiptSignatureScheme<N>, N: FieldExt + PrimeFieldBits> ReceiptCircuit<S, N>
+where
+    P128Pow5T3: Spec<N, P128_WIDTH, P128_RATE>,
+{
+    /// Verifies the receipt signatures, registry membership, expiry and
+    /// amount range, returning the cells that may be exposed.
+    fn verify_receipt(
+        &self,
+        config: &ReceiptConfig<N, S>,
+        layouter: &mut impl Layouter<N>,
+    ) -> Result<AssignedReceipt<N>, Error> {
+        let main_gate = MainGate::<N>::new(config.main_gate_config.clone());
+        let signature_chip = self.scheme.load_chip(&config.signature_config, layouter)?;
+        let poseidon_chip = PoseidonChip::<N, P128Pow5T3, P128_WIDTH, P128_RATE>::new(
+            config.main_gate_config.clone(),
+        );
+        let merkle_chip = MerkleChip::new(config.main_gate_config.clone());
+
+        let receipt = layouter.assign_region(
+            || "verify receipt",
+            |mut region| {
+                let offset = &mut 0;
+                let ctx = &mut RegionCtx::new(&mut region, offset);
+
+                let a_index = main_gate.assign_value(ctx, self.a_index)?;
+                let b_index = main_gate.assign_value(ctx, self.b_index)?;
+                let amount = main_gate.assign_value(ctx, self.amount)?;
+                let expires_by = main_gate.assign_value(ctx, self.expires_by)?;
+                let now = main_gate.assign_value(ctx, self.now)?;
+                let nullifier_secret = main_gate.assign_value(ctx, self.nullifier_secret)?;
+
+                let receipt_tag = poseidon_chip.domain_tag(ctx, Domain::Receipt)?;
+                let hash = poseidon_chip.hash(
+                    ctx,
+                    &[
+                        receipt_tag,
+                        a_index.clone(),
+                        b_index.clone(),
+                        amount.clone(),
+                        expires_by.clone(),
+                    ],
+                )?;
+
+                let b_public_key = S::assign_public_key(&signature_chip, ctx, self.b_public_key)?;
+                let b_signature = S::assign_signature(&signature_chip, ctx, self.b_signature)?;
+                S::verify(&signature_chip, ctx, &b_public_key, &hash, &b_signature)?;
+                let b_public_key = S::public_key_cells(&b_public_key);
+
+                let a_public_key = match self.signature_mode {
+                    SignatureMode::PayeeOnly => None,
+                    SignatureMode::CoSigned => {
+                        let a_public_key =
+                            S::assign_public_key(&signature_chip, ctx, self.a_public_key)?;
+                        let a_signature =
+                            S::assign_signature(&signature_chip, ctx, self.a_signature)?;
+                        S::verify(&signature_chip, ctx, &a_public_key, &hash, &a_signature)?;
+                        Some(S::public_key_cells(&a_public_key))
+                    }
+                };
+
+                let secret_commitment = merkle_chip.secret_commitment(ctx, &nullifier_secret)?;
+                let nullifier_tag = poseidon_chip.domain_tag(ctx, Domain::Nullifier)?;
+                let nullifier =
+                    poseidon_chip.hash(ctx, &[nullifier_tag, nullifier_secret, hash])?;
+
+                let leaf =
+                    merkle_chip.registry_leaf(ctx, &b_index, &b_public_key, &secret_commitment)?;
+                let registry_root =
+                    merkle_chip.compute_root(ctx, &leaf, &b_index, &self.b_registry_path)?;
+
+                Ok(AssignedReceipt {
+                    b_public_key,
+                    a_public_key,
+                    a_index,
+                    b_index,
+                    amount,
+                    expires_by,
+                    now,
+                    nullifier,
+                    registry_root,
+                })
+            },
+        )?;
+
+        let less_than_chip =
+            LessThanChip::new(config.main_gate_config.clone(), config.running_sum_config);
+        less_than_chip.assert_less_than(
+            layouter.namespace(|| "now < expires_by"),
+            &receipt.now,
+            &receipt.expires_by,
+            self.timestamp_num_bits,
+        )?;
+        config.running_sum_config.range_check(
+            layouter.namespace(|| "amount"),
+            &receipt.amount,
+            self.amount_num_bits,
+        )?;
+
+        Ok(receipt)
+    }
 }
 
-impl<E: CurveAffine, N: FieldExt> Circuit<N> for ReceiptCircuit<E, N> {
-    type Config = ReceiptConfig;
+impl<S: ReceiptSignatureScheme<N>, N: FieldExt + PrimeFieldBits> Circuit<N> for ReceiptCircuit<S, N>
+where
+    P128Pow5T3: Spec<N, P128_WIDTH, P128_RATE>,
+{
+    type Config = ReceiptConfig<N, S>;
     type FloorPlanner = SimpleFloorPlanner;
 
     fn without_witnesses(&self) -> Self {
-        Self::default()
+        Self {
+            expose_expires_by: self.expose_expires_by,
+            signature_mode: self.signature_mode,
+            timestamp_num_bits: self.timestamp_num_bits,
+            amount_num_bits: self.amount_num_bits,
+            scheme: self.scheme,
+            b_registry_path: vec![Value::unknown(); self.b_registry_path.len()],
+            ..Default::default()
+        }
     }
 
-    fn configure(meta: &mut halo2::plonk::ConstraintSystem<N>) -> Self::Config {
-        todo!()
+    fn configure(meta: &mut ConstraintSystem<N>) -> Self::Config {
+        ReceiptConfig::configure(meta)
     }
 
     fn synthesize(
         &self,
         config: Self::Config,
-        layouter: impl halo2::circuit::Layouter<N>,
-    ) -> Result<(), halo2::plonk::Error> {
-        todo!()
+        mut layouter: impl Layouter<N>,
+    ) -> Result<(), Error> {
+        let receipt = self.verify_receipt(&config, &mut layouter)?;
+
+        let public_inputs = receipt.public_inputs(&[&receipt.amount], self.expose_expires_by);
+        config.expose_public_inputs(&mut layouter, &public_inputs)?;
+
+        S::load_tables(&config.signature_config, &mut layouter)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::marker::PhantomData;
-
-    use ecc::{
-        integer::{rns::Rns, Range},
-        EccConfig, GeneralEccChip,
-    };
-    use ecdsa::ecdsa::{AssignedEcdsaSig, AssignedPublicKey, EcdsaChip};
-    use ff::PrimeField;
+    use ecc::integer::rns::Rns;
+    use ff::PrimeFieldBits;
     use halo2::{
         arithmetic::{CurveAffine, Field, FieldExt},
         circuit::{SimpleFloorPlanner, Value},
-        dev::{CircuitLayout, MockProver},
+        dev::MockProver,
         halo2curves::{
-            group::{Curve, Group},
-            pasta::Fp as PastaFp,
+            group::Curve,
+            pasta::{pallas, Fp as PastaFp, Fq},
             secp256k1::Secp256k1Affine,
+            secp256r1::Secp256r1Affine,
         },
         plonk::Circuit,
     };
-    use integer::IntegerInstructions;
-    use maingate::{
-        MainGate, MainGateConfig, RangeChip, RangeConfig, RangeInstructions, RegionCtx,
-    };
-    use num_bigint::BigUint;
-    use num_traits::Num;
+    use maingate::{MainGate, MainGateConfig, RangeChip, RangeConfig};
     use rand_core::OsRng;
 
-    const BIT_LEN_LIMB: usize = 68;
-    const NUMBER_OF_LIMBS: usize = 4;
-
-    #[derive(Clone, Debug)]
-    struct EcdsaConfig {
-        maingate_config: MainGateConfig,
-        rangecheck_config: RangeConfig,
-    }
-
-    #[derive(Clone, Debug, Default)]
-    struct EcdsaCircuit<E: CurveAffine, N: FieldExt> {
-        public_key: Value<E>,
-        signature: Value<(E::Scalar, E::Scalar)>,
-        msg_hash: Value<E::Scalar>,
-
-        aux_generator: E,
-        window_size: usize,
-        _marker: PhantomData<N>,
-    }
-
-    impl<E: CurveAffine, N: FieldExt> Circuit<N> for EcdsaCircuit<E, N> {
-        type Config = EcdsaConfig;
-        type FloorPlanner = SimpleFloorPlanner;
-
-        fn configure(meta: &mut ecc::halo2::plonk::ConstraintSystem<N>) -> Self::Config {
-            let (rns_base, rns_scalar) =
-                GeneralEccChip::<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>::rns();
-            let maingate_config = MainGate::<N>::configure(meta);
-            let mut overflow_bit_lens: Vec<usize> = vec![];
-            overflow_bit_lens.extend(rns_base.overflow_lengths());
-            overflow_bit_lens.extend(rns_scalar.overflow_lengths());
-            let composition_bit_lens = vec![BIT_LEN_LIMB / NUMBER_OF_LIMBS];
-
-            let rangecheck_config = RangeChip::<N>::configure(
-                meta,
-                &maingate_config,
-                composition_bit_lens,
-                overflow_bit_lens,
-            );
-
-            EcdsaConfig {
-                maingate_config,
-                rangecheck_config,
-            }
-        }
-
-        fn without_witnesses(&self) -> Self {
-            Self::default()
-        }
-
-        fn synthesize(
-            &self,
-            config: Self::Config,
-            mut layouter: impl halo2::circuit::Layouter<N>,
-        ) -> Result<(), halo2::plonk::Error> {
-            let ecc_config = EccConfig::new(
-                config.rangecheck_config.clone(),
-                config.maingate_config.clone(),
-            );
-            let mut ecc_chip =
-                GeneralEccChip::<E, N, NUMBER_OF_LIMBS, BIT_LEN_LIMB>::new(ecc_config);
-            let scalar_chip = ecc_chip.scalar_field_chip();
-
-            layouter.assign_region(
-                || "assign aux values",
-                |mut region| {
-                    let offset = &mut 0;
-                    let ctx = &mut RegionCtx::new(&mut region, offset);
-
-                    ecc_chip.assign_aux_generator(ctx, Value::known(self.aux_generator))?;
-                    ecc_chip.assign_aux(ctx, self.window_size, 1)?;
-                    Ok(())
-                },
-            )?;
-
-            let ecdsa_chip = EcdsaChip::new(ecc_chip.clone());
-
-            layouter.assign_region(
-                || "region 0",
-                |mut region| {
-                    let offset = &mut 0;
-                    let ctx = &mut RegionCtx::new(&mut region, offset);
-
-                    let r = self.signature.map(|signature| signature.0);
-                    let s = self.signature.map(|signature| signature.1);
-                    let integer_r = ecc_chip.new_unassigned_scalar(r);
-                    let integer_s = ecc_chip.new_unassigned_scalar(s);
-                    let msg_hash = ecc_chip.new_unassigned_scalar(self.msg_hash);
-
-                    let r_assigned =
-                        scalar_chip.assign_integer(ctx, integer_r, Range::Remainder)?;
-                    let s_assigned =
-                        scalar_chip.assign_integer(ctx, integer_s, Range::Remainder)?;
-                    let sig = AssignedEcdsaSig {
-                        r: r_assigned,
-                        s: s_assigned,
-                    };
-
-                    let pk_in_circuit = ecc_chip.assign_point(ctx, self.public_key)?;
-                    let pk_assigned = AssignedPublicKey {
-                        point: pk_in_circuit,
-                    };
-                    let msg_hash = scalar_chip.assign_integer(ctx, msg_hash, Range::Remainder)?;
-                    ecdsa_chip.verify(ctx, &sig, &pk_assigned, &msg_hash)
-                },
-            )?;
-
-            // configure range check
-            let range_chip = RangeChip::<N>::new(config.rangecheck_config.clone());
-            range_chip.load_composition_tables(&mut layouter)?;
-            range_chip.load_overflow_tables(&mut layouter)?;
-
-            Ok(())
-        }
-    }
+    use super::{
+        merkle::{registry_leaf, scheme_registry_leaf, secret_commitment, IncrementalMerkleTree},
+        poseidon::{P128Pow5T3, Spec, P128_RATE, P128_WIDTH},
+        schnorr::{self, Schnorr},
+        Receipt, ReceiptCircuit, ReceiptPublicInputs, SignatureMode, BIT_LEN_LIMB, NUMBER_OF_LIMBS,
+    };
 
     // RSA verification
     #[derive(Clone, Debug)]
@@ -260,82 +473,397 @@ mod tests {
     }
 
     #[test]
-    fn test_ecdsa() {
-        // from group's base field to scalar field
-        fn mod_n<C: CurveAffine>(x: C::Base) -> C::Scalar {
-            // convert to bigint
-            let x = BigUint::from_bytes_le(x.to_repr().as_ref());
-
-            // calculate mod scalar modulus
-            let modulus =
-                BigUint::from_str_radix(&<C::Scalar as FieldExt>::MODULUS[2..], 16).unwrap();
-            let x = x % modulus;
-
-            <C::Scalar as PrimeField>::from_str_vartime(&x.to_str_radix(10)[..]).unwrap()
-        }
-
-        fn run<C: CurveAffine, N: FieldExt>() {
+    fn test_receipt() {
+        fn run<C: CurveAffine, N: FieldExt + PrimeFieldBits>()
+        where
+            P128Pow5T3: Spec<N, P128_WIDTH, P128_RATE>,
+        {
             let g = C::generator();
-            // keypair
             let sk = <C as CurveAffine>::ScalarExt::random(OsRng);
             let pk = (g * sk).to_affine();
+            let now = N::from(1_600_000_000);
+            let nullifier_secret = N::random(OsRng);
+
+            let receipt = Receipt {
+                a_index: N::from(1),
+                b_index: N::from(2),
+                amount: N::from(100),
+                expires_by: N::from(1_700_000_000),
+            };
+            let Receipt {
+                b_index,
+                amount,
+                expires_by,
+                ..
+            } = receipt;
+
+            let signature = receipt.sign::<C>(sk);
+
+            // `b_public_key` registered at `b_index`, next to another account
+            let other_pk = (g * C::Scalar::random(OsRng)).to_affine();
+            let mut registry = IncrementalMerkleTree::new(8);
+            registry.insert(N::random(OsRng));
+            registry.insert(registry_leaf(
+                N::from(1),
+                other_pk,
+                secret_commitment(N::random(OsRng)),
+            ));
+            registry.insert(registry_leaf(
+                b_index,
+                pk,
+                secret_commitment(nullifier_secret),
+            ));
+            let path = registry.path(2);
+            let root = registry.root();
+
+            let k = 20;
+            let circuit = ReceiptCircuit {
+                timestamp_num_bits: 40,
+                ..receipt.to_circuit(pk, signature, now, nullifier_secret, &path)
+            };
+            let public_inputs = receipt.public_inputs(pk, now, nullifier_secret, root);
+
+            // valid receipt
+            let prover = match MockProver::run(k, &circuit, public_inputs.instances()) {
+                Ok(prover) => prover,
+                Err(e) => panic!("{:#?}", e),
+            };
+            assert_eq!(prover.verify(), Ok(()));
 
-            // random msg_hash
-            let msg_hash = <C as CurveAffine>::ScalarExt::random(OsRng);
+            // tampered receipt fields no longer match the signature
+            for tampered in [
+                ReceiptCircuit {
+                    amount: Value::known(amount + N::one()),
+                    ..circuit.clone()
+                },
+                ReceiptCircuit {
+                    expires_by: Value::known(expires_by + N::one()),
+                    ..circuit.clone()
+                },
+                // a sum-based hash would accept swapped fields
+                ReceiptCircuit {
+                    b_index: Value::known(amount),
+                    amount: Value::known(b_index),
+                    ..circuit.clone()
+                },
+            ] {
+                let prover = MockProver::run(k, &tampered, public_inputs.instances()).unwrap();
+                assert!(prover.verify().is_err());
+            }
 
-            // Generate sig
+            // public inputs that don't match the signed receipt
+            let tampered_inputs = ReceiptPublicInputs {
+                amount: amount + N::one(),
+                ..public_inputs
+            };
+            let prover = MockProver::run(k, &circuit, tampered_inputs.instances()).unwrap();
+            assert!(prover.verify().is_err());
 
-            // k
-            let k = <C as CurveAffine>::ScalarExt::random(OsRng);
-            let k_inv = k.invert().unwrap();
+            // signature by a different key
+            let tampered = ReceiptCircuit {
+                b_public_key: Value::known(other_pk),
+                ..circuit.clone()
+            };
+            let prover = MockProver::run(k, &tampered, public_inputs.instances()).unwrap();
+            assert!(prover.verify().is_err());
+
+            // nullifier that doesn't match the receipt and secret
+            for nullifier in [
+                receipt.nullifier(N::random(OsRng)),
+                Receipt {
+                    amount: amount + N::one(),
+                    ..receipt
+                }
+                .nullifier(nullifier_secret),
+                receipt.hash(),
+            ] {
+                let tampered_inputs = ReceiptPublicInputs {
+                    nullifier,
+                    ..public_inputs
+                };
+                let prover = MockProver::run(k, &circuit, tampered_inputs.instances()).unwrap();
+                assert!(prover.verify().is_err());
+            }
 
-            // r
-            let rpoint = (g * k).to_affine().coordinates().unwrap();
-            let x = rpoint.x();
-            let r = mod_n::<C>(*x);
+            // secret other than the registered one, with its own nullifier
+            let other_secret = N::random(OsRng);
+            let tampered = ReceiptCircuit {
+                nullifier_secret: Value::known(other_secret),
+                ..circuit.clone()
+            };
+            let tampered_inputs = receipt.public_inputs(pk, now, other_secret, root);
+            let prover = MockProver::run(k, &tampered, tampered_inputs.instances()).unwrap();
+            assert!(prover.verify().is_err());
+
+            // registry root other than the one the key is registered in
+            let tampered_inputs = ReceiptPublicInputs {
+                registry_root: root + N::one(),
+                ..public_inputs
+            };
+            let prover = MockProver::run(k, &circuit, tampered_inputs.instances()).unwrap();
+            assert!(prover.verify().is_err());
+
+            // key that isn't registered, with its own valid signature
+            let unregistered_sk = C::Scalar::random(OsRng);
+            let unregistered_pk = (g * unregistered_sk).to_affine();
+            let unregistered = receipt.to_circuit(
+                unregistered_pk,
+                receipt.sign::<C>(unregistered_sk),
+                now,
+                nullifier_secret,
+                &path,
+            );
+            let unregistered_inputs = ReceiptPublicInputs {
+                b_public_key: unregistered_pk,
+                ..public_inputs
+            };
+            let prover =
+                MockProver::run(k, &unregistered, unregistered_inputs.instances()).unwrap();
+            assert!(prover.verify().is_err());
+
+            // path of another account's leaf
+            let tampered = ReceiptCircuit {
+                b_registry_path: registry.path(1).into_iter().map(Value::known).collect(),
+                ..circuit.clone()
+            };
+            let prover = MockProver::run(k, &tampered, public_inputs.instances()).unwrap();
+            assert!(prover.verify().is_err());
+
+            // expired receipt
+            for now in [expires_by, expires_by + N::one()] {
+                let expired = ReceiptCircuit {
+                    now: Value::known(now),
+                    ..circuit.clone()
+                };
+                let public_inputs = ReceiptPublicInputs {
+                    now,
+                    ..public_inputs
+                };
+                let prover = MockProver::run(k, &expired, public_inputs.instances()).unwrap();
+                assert!(prover.verify().is_err());
+            }
 
-            // s
-            let s = k_inv * (msg_hash + (r * sk));
+            // `now` that doesn't match the public input
+            let tampered_inputs = ReceiptPublicInputs {
+                now: now + N::one(),
+                ..public_inputs
+            };
+            let prover = MockProver::run(k, &circuit, tampered_inputs.instances()).unwrap();
+            assert!(prover.verify().is_err());
 
-            {
-                // Ensuring signature is valid
-                let s_inv = s.invert().unwrap();
-                let u_1 = msg_hash * s_inv;
-                let u_2 = r * s_inv;
-                let r_point = ((g * u_1) + (pk * u_2)).to_affine().coordinates().unwrap();
-                let x_candidate = r_point.x();
-                let r_candidate = mod_n::<C>(*x_candidate);
-                assert_eq!(r, r_candidate);
+            // 64-bit timestamps
+            let circuit_64 = ReceiptCircuit {
+                timestamp_num_bits: 64,
+                ..circuit.clone()
+            };
+            let prover = MockProver::run(k, &circuit_64, public_inputs.instances()).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+
+            // amounts that don't fit in `amount_num_bits`, even if signed
+            for (amount, amount_num_bits) in [
+                (-N::one(), 64),
+                (-N::one(), 128),
+                (N::from_u128(1 << 64), 64),
+            ] {
+                let receipt = Receipt { amount, ..receipt };
+                let wrapped = ReceiptCircuit {
+                    amount_num_bits,
+                    ..receipt.to_circuit(pk, receipt.sign::<C>(sk), now, nullifier_secret, &path)
+                };
+                let public_inputs = receipt.public_inputs(pk, now, nullifier_secret, root);
+                let prover = MockProver::run(k, &wrapped, public_inputs.instances()).unwrap();
+                assert!(prover.verify().is_err());
             }
 
-            // prove valid signature
-            let k = 20;
-            let aux_generator = C::CurveExt::random(OsRng).to_affine();
-            let ecdsa_circuit = EcdsaCircuit::<C, N> {
-                public_key: Value::known(pk),
-                msg_hash: Value::known(msg_hash),
-                signature: Value::known((r, s)),
-
-                aux_generator,
-                window_size: 2,
-                _marker: PhantomData,
+            // u128 amounts
+            let receipt_128 = Receipt {
+                amount: N::from_u128(1 << 64),
+                ..receipt
+            };
+            let circuit_128 = ReceiptCircuit {
+                amount_num_bits: 128,
+                ..receipt_128.to_circuit(
+                    pk,
+                    receipt_128.sign::<C>(sk),
+                    now,
+                    nullifier_secret,
+                    &path,
+                )
+            };
+            let public_inputs_128 = receipt_128.public_inputs(pk, now, nullifier_secret, root);
+            let prover = MockProver::run(k, &circuit_128, public_inputs_128.instances()).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+
+            // expires_by exposed as a public input
+            let circuit = ReceiptCircuit {
+                expose_expires_by: true,
+                ..circuit
             };
+            let public_inputs = ReceiptPublicInputs {
+                expires_by: Some(expires_by),
+                ..public_inputs
+            };
+            let prover = MockProver::run(k, &circuit, public_inputs.instances()).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
 
-            // print circuit
-            use plotters::prelude::*;
-            let root = BitMapBackend::new("target/ecdsa.png", (3840, 2160)).into_drawing_area();
-            CircuitLayout::default()
-                .render(k, &ecdsa_circuit, &root)
-                .unwrap();
-
-            // let public_inputs = vec![vec![]];
-            // let prover = match MockProver::run(k, &ecdsa_circuit, public_inputs) {
-            //     Ok(prover) => prover,
-            //     Err(e) => panic!("{:#?}", e),
-            // };
-            // assert_eq!(prover.verify(), Ok(()));
+            let tampered_inputs = ReceiptPublicInputs {
+                expires_by: Some(expires_by + N::one()),
+                ..public_inputs
+            };
+            let prover = MockProver::run(k, &circuit, tampered_inputs.instances()).unwrap();
+            assert!(prover.verify().is_err());
         }
 
         run::<Secp256k1Affine, PastaFp>();
+        run::<Secp256r1Affine, PastaFp>();
+    }
+
+    #[test]
+    fn test_schnorr_receipt() {
+        let g = pallas::Affine::generator();
+        let sk = Fq::random(OsRng);
+        let pk = (g * sk).to_affine();
+        let now = PastaFp::from(1_600_000_000);
+        let nullifier_secret = PastaFp::random(OsRng);
+
+        let receipt = Receipt {
+            a_index: PastaFp::from(1),
+            b_index: PastaFp::from(2),
+            amount: PastaFp::from(100),
+            expires_by: PastaFp::from(1_700_000_000),
+        };
+
+        let mut registry = IncrementalMerkleTree::new(8);
+        registry.insert(PastaFp::random(OsRng));
+        registry.insert(PastaFp::random(OsRng));
+        registry.insert(scheme_registry_leaf::<Schnorr, _>(
+            receipt.b_index,
+            &pk,
+            secret_commitment(nullifier_secret),
+        ));
+        let path = registry.path(2);
+        let root = registry.root();
+
+        let k = 15;
+        let circuit = receipt.to_circuit_with(
+            Schnorr,
+            pk,
+            schnorr::sign(sk, receipt.hash()),
+            now,
+            nullifier_secret,
+            &path,
+        );
+        let public_inputs = receipt.public_inputs_with::<Schnorr>(pk, now, nullifier_secret, root);
+        let prover = MockProver::run(k, &circuit, public_inputs.instances()).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // signature over another receipt
+        let other = Receipt {
+            amount: receipt.amount + PastaFp::one(),
+            ..receipt
+        };
+        let tampered = ReceiptCircuit {
+            b_signature: Value::known(schnorr::sign(sk, other.hash())),
+            ..circuit.clone()
+        };
+        let prover = MockProver::run(k, &tampered, public_inputs.instances()).unwrap();
+        assert!(prover.verify().is_err());
+
+        // public key other than the signer's
+        let tampered_inputs = ReceiptPublicInputs {
+            b_public_key: (g * Fq::random(OsRng)).to_affine(),
+            ..public_inputs
+        };
+        let prover = MockProver::run(k, &circuit, tampered_inputs.instances()).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_co_signed_receipt() {
+        let g = pallas::Affine::generator();
+        let a_sk = Fq::random(OsRng);
+        let a_pk = (g * a_sk).to_affine();
+        let b_sk = Fq::random(OsRng);
+        let b_pk = (g * b_sk).to_affine();
+        let now = PastaFp::from(1_600_000_000);
+        let nullifier_secret = PastaFp::random(OsRng);
+
+        let receipt = Receipt {
+            a_index: PastaFp::from(1),
+            b_index: PastaFp::from(2),
+            amount: PastaFp::from(100),
+            expires_by: PastaFp::from(1_700_000_000),
+        };
+
+        let mut registry = IncrementalMerkleTree::new(8);
+        registry.insert(PastaFp::random(OsRng));
+        registry.insert(scheme_registry_leaf::<Schnorr, _>(
+            receipt.a_index,
+            &a_pk,
+            secret_commitment(PastaFp::random(OsRng)),
+        ));
+        registry.insert(scheme_registry_leaf::<Schnorr, _>(
+            receipt.b_index,
+            &b_pk,
+            secret_commitment(nullifier_secret),
+        ));
+        let path = registry.path(2);
+        let root = registry.root();
+
+        let k = 15;
+        let circuit = ReceiptCircuit {
+            signature_mode: SignatureMode::CoSigned,
+            a_public_key: Value::known(a_pk),
+            a_signature: Value::known(schnorr::sign(a_sk, receipt.hash())),
+            ..receipt.to_circuit_with(
+                Schnorr,
+                b_pk,
+                schnorr::sign(b_sk, receipt.hash()),
+                now,
+                nullifier_secret,
+                &path,
+            )
+        };
+        let public_inputs = ReceiptPublicInputs {
+            a_public_key: Some(a_pk),
+            ..receipt.public_inputs_with::<Schnorr>(b_pk, now, nullifier_secret, root)
+        };
+        let prover = MockProver::run(k, &circuit, public_inputs.instances()).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // payer signature over another receipt, or by another key
+        let other = Receipt {
+            amount: receipt.amount + PastaFp::one(),
+            ..receipt
+        };
+        for a_signature in [
+            schnorr::sign(a_sk, other.hash()),
+            schnorr::sign(b_sk, receipt.hash()),
+        ] {
+            let tampered = ReceiptCircuit {
+                a_signature: Value::known(a_signature),
+                ..circuit.clone()
+            };
+            let prover = MockProver::run(k, &tampered, public_inputs.instances()).unwrap();
+            assert!(prover.verify().is_err());
+        }
+
+        // payer key missing from or other than the one exposed
+        for a_public_key in [None, Some(b_pk)] {
+            let tampered_inputs = ReceiptPublicInputs {
+                a_public_key,
+                ..public_inputs
+            };
+            let prover = MockProver::run(k, &circuit, tampered_inputs.instances()).unwrap();
+            assert!(prover.verify().is_err());
+        }
+
+        // the payee signature is still required
+        let tampered = ReceiptCircuit {
+            b_signature: circuit.a_signature,
+            ..circuit.clone()
+        };
+        let prover = MockProver::run(k, &tampered, public_inputs.instances()).unwrap();
+        assert!(prover.verify().is_err());
     }
 }
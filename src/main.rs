@@ -0,0 +1,407 @@
+//! Command-line interface to the receipt circuit.
+//!
+//! ```text
+//! halo2-ops keygen --keys <dir>
+//! halo2-ops commit --nullifier-secret <hex>
+//! halo2-ops sign   --receipt <receipt.json> --secret-key <hex> --out <signed.json>
+//! halo2-ops prove  --keys <dir> --signed <signed.json> --registry <registry.json>
+//!                  --now <timestamp> --nullifier-secret <hex> --proof <proof.bin>
+//!                  --public-inputs <public.json>
+//! halo2-ops verify --keys <dir> --public-inputs <public.json> --proof <proof.bin>
+//! ```
+//!
+//! The registry is a JSON array of accounts where account `i` is at position
+//! `i`. Each account has a public key and the commitment to its nullifier
+//! secret printed by `commit`, and receipts of the account can only be proven
+//! with that secret. `verify` checks the proof against the registry root
+//! in the public inputs, which the caller must compare to a trusted root.
+//!
+//! Field elements and scalars are encoded as hex of their little-endian
+//! representation.
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    process,
+};
+
+use ff::PrimeField;
+use halo2::{
+    arithmetic::CurveAffine,
+    circuit::Value,
+    halo2curves::{group::Curve, pasta::Fp as PastaFp, secp256k1::Secp256k1Affine},
+};
+use halo2_ops::{
+    keys,
+    merkle::{registry_leaf, secret_commitment, IncrementalMerkleTree},
+    prover::{keygen, prove_receipt, setup, verify_receipt, RECEIPT_K},
+    Ecdsa, Receipt, ReceiptCircuit, ReceiptPublicInputs, DEFAULT_AMOUNT_NUM_BITS,
+    DEFAULT_REGISTRY_DEPTH, DEFAULT_TIMESTAMP_NUM_BITS, DEFAULT_WINDOW_SIZE,
+};
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+
+type Scalar = <Secp256k1Affine as CurveAffine>::ScalarExt;
+
+const USAGE: &str = "usage:
+  halo2-ops keygen --keys <dir>
+  halo2-ops commit --nullifier-secret <hex>
+  halo2-ops sign   --receipt <receipt.json> --secret-key <hex> --out <signed.json>
+  halo2-ops prove  --keys <dir> --signed <signed.json> --registry <registry.json> --now <timestamp> --nullifier-secret <hex> --proof <proof.bin> --public-inputs <public.json>
+  halo2-ops verify --keys <dir> --public-inputs <public.json> --proof <proof.bin>";
+
+/// Receipt description, as accepted by `sign`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+struct ReceiptJson {
+    a_index: u64,
+    b_index: u64,
+    amount: u64,
+    expires_by: u64,
+}
+
+impl ReceiptJson {
+    fn to_receipt(self) -> Receipt<PastaFp> {
+        Receipt {
+            a_index: PastaFp::from(self.a_index),
+            b_index: PastaFp::from(self.b_index),
+            amount: PastaFp::from(self.amount),
+            expires_by: PastaFp::from(self.expires_by),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct PublicKeyJson {
+    x: String,
+    y: String,
+}
+
+impl PublicKeyJson {
+    fn from_point(point: Secp256k1Affine) -> Self {
+        let coordinates = point.coordinates().unwrap();
+        Self {
+            x: encode(coordinates.x()),
+            y: encode(coordinates.y()),
+        }
+    }
+
+    fn to_point(&self) -> Result<Secp256k1Affine, Box<dyn Error>> {
+        let point = Secp256k1Affine::from_xy(decode(&self.x)?, decode(&self.y)?);
+        Option::from(point).ok_or_else(|| "public key is not on the curve".into())
+    }
+}
+
+/// Account of the registry.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct AccountJson {
+    public_key: PublicKeyJson,
+    secret_commitment: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct SignatureJson {
+    r: String,
+    s: String,
+}
+
+/// Output of `sign`, input of `prove`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct SignedReceiptJson {
+    receipt: ReceiptJson,
+    b_public_key: PublicKeyJson,
+    b_signature: SignatureJson,
+}
+
+/// Output of `prove`, input of `verify`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct PublicInputsJson {
+    b_public_key: PublicKeyJson,
+    a_index: u64,
+    b_index: u64,
+    amount: u64,
+    now: u64,
+    nullifier: String,
+    registry_root: String,
+}
+
+impl PublicInputsJson {
+    fn to_public_inputs(
+        &self,
+    ) -> Result<ReceiptPublicInputs<Ecdsa<Secp256k1Affine>, PastaFp>, Box<dyn Error>> {
+        Ok(ReceiptPublicInputs {
+            b_public_key: self.b_public_key.to_point()?,
+            a_index: PastaFp::from(self.a_index),
+            b_index: PastaFp::from(self.b_index),
+            amount: PastaFp::from(self.amount),
+            now: PastaFp::from(self.now),
+            nullifier: decode(&self.nullifier)?,
+            registry_root: decode(&self.registry_root)?,
+            expires_by: None,
+            a_public_key: None,
+        })
+    }
+}
+
+fn encode<F: PrimeField>(fe: &F) -> String {
+    hex::encode(fe.to_repr())
+}
+
+fn decode<F: PrimeField>(s: &str) -> Result<F, Box<dyn Error>> {
+    let bytes = hex::decode(s)?;
+    let mut repr = F::Repr::default();
+    if bytes.len() != repr.as_ref().len() {
+        return Err(format!(
+            "expected {} bytes, found {}",
+            repr.as_ref().len(),
+            bytes.len()
+        )
+        .into());
+    }
+    repr.as_mut().copy_from_slice(&bytes);
+    Option::from(F::from_repr(repr)).ok_or_else(|| "non-canonical field element".into())
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<T, Box<dyn Error>> {
+    Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), Box<dyn Error>> {
+    Ok(serde_json::to_writer_pretty(
+        BufWriter::new(File::create(path)?),
+        value,
+    )?)
+}
+
+/// Shape of the circuit the keys are generated for.
+fn circuit_shape() -> ReceiptCircuit<Ecdsa<Secp256k1Affine>, PastaFp> {
+    ReceiptCircuit {
+        timestamp_num_bits: DEFAULT_TIMESTAMP_NUM_BITS,
+        amount_num_bits: DEFAULT_AMOUNT_NUM_BITS,
+        scheme: Ecdsa::new(DEFAULT_WINDOW_SIZE),
+        b_registry_path: vec![Value::unknown(); DEFAULT_REGISTRY_DEPTH],
+        ..Default::default()
+    }
+}
+
+/// Registry tree of the accounts in `registry`, indexed by position.
+fn registry_tree(
+    registry: &[AccountJson],
+) -> Result<IncrementalMerkleTree<PastaFp>, Box<dyn Error>> {
+    if registry.len() > 1 << DEFAULT_REGISTRY_DEPTH {
+        return Err("too many accounts in the registry".into());
+    }
+
+    let mut tree = IncrementalMerkleTree::new(DEFAULT_REGISTRY_DEPTH);
+    for (index, account) in registry.iter().enumerate() {
+        tree.insert(registry_leaf(
+            PastaFp::from(index as u64),
+            account.public_key.to_point()?,
+            decode(&account.secret_commitment)?,
+        ));
+    }
+    Ok(tree)
+}
+
+struct Args(HashMap<String, String>);
+
+impl Args {
+    fn parse(args: &[String]) -> Result<Self, Box<dyn Error>> {
+        let mut parsed = HashMap::new();
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let name = flag
+                .strip_prefix("--")
+                .ok_or_else(|| format!("unexpected argument `{}`", flag))?;
+            let value = args
+                .next()
+                .ok_or_else(|| format!("missing value for `{}`", flag))?;
+            parsed.insert(name.to_string(), value.clone());
+        }
+        Ok(Self(parsed))
+    }
+
+    fn get(&self, name: &str) -> Result<&str, Box<dyn Error>> {
+        self.0
+            .get(name)
+            .map(|value| value.as_str())
+            .ok_or_else(|| format!("missing `--{}`", name).into())
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf, Box<dyn Error>> {
+        self.get(name).map(PathBuf::from)
+    }
+}
+
+fn cmd_keygen(args: &Args) -> Result<(), Box<dyn Error>> {
+    let dir = args.path("keys")?;
+    fs::create_dir_all(&dir)?;
+
+    let circuit = circuit_shape();
+    let params = setup(RECEIPT_K);
+    let pk = keygen(&params, &circuit)?;
+
+    keys::write_params(
+        &mut BufWriter::new(File::create(dir.join("params.bin"))?),
+        &params,
+    )?;
+    keys::write_vk(
+        &mut BufWriter::new(File::create(dir.join("vk.bin"))?),
+        pk.get_vk(),
+        RECEIPT_K,
+        &circuit,
+    )?;
+    keys::write_pk(
+        &mut BufWriter::new(File::create(dir.join("pk.bin"))?),
+        &pk,
+        RECEIPT_K,
+        &circuit,
+    )?;
+    Ok(())
+}
+
+fn cmd_commit(args: &Args) -> Result<(), Box<dyn Error>> {
+    let nullifier_secret: PastaFp = decode(args.get("nullifier-secret")?)?;
+    println!("{}", encode(&secret_commitment(nullifier_secret)));
+    Ok(())
+}
+
+fn cmd_sign(args: &Args) -> Result<(), Box<dyn Error>> {
+    let receipt: ReceiptJson = read_json(&args.path("receipt")?)?;
+    let sk: Scalar = decode(args.get("secret-key")?)?;
+    let pk = (Secp256k1Affine::generator() * sk).to_affine();
+
+    let (r, s) = receipt.to_receipt().sign::<Secp256k1Affine>(sk);
+    let signed = SignedReceiptJson {
+        receipt,
+        b_public_key: PublicKeyJson::from_point(pk),
+        b_signature: SignatureJson {
+            r: encode(&r),
+            s: encode(&s),
+        },
+    };
+    write_json(&args.path("out")?, &signed)
+}
+
+fn cmd_prove(args: &Args) -> Result<(), Box<dyn Error>> {
+    let dir = args.path("keys")?;
+    let signed: SignedReceiptJson = read_json(&args.path("signed")?)?;
+    let registry: Vec<AccountJson> = read_json(&args.path("registry")?)?;
+    let now: u64 = args.get("now")?.parse()?;
+    let nullifier_secret: PastaFp = decode(args.get("nullifier-secret")?)?;
+
+    let receipt = signed.receipt.to_receipt();
+    let pk = signed.b_public_key.to_point()?;
+    let signature: (Scalar, Scalar) = (
+        decode(&signed.b_signature.r)?,
+        decode(&signed.b_signature.s)?,
+    );
+    if !receipt.verify(pk, signature) {
+        return Err("invalid receipt signature".into());
+    }
+    let account = registry
+        .get(signed.receipt.b_index as usize)
+        .ok_or("no account is registered at `b_index`")?;
+    if account.public_key.to_point()? != pk {
+        return Err("public key isn't registered at `b_index`".into());
+    }
+    if decode::<PastaFp>(&account.secret_commitment)? != secret_commitment(nullifier_secret) {
+        return Err("nullifier secret isn't registered at `b_index`".into());
+    }
+    let registry = registry_tree(&registry)?;
+
+    let shape = circuit_shape();
+    let params = keys::read_params(
+        &mut BufReader::new(File::open(dir.join("params.bin"))?),
+        RECEIPT_K,
+    )?;
+    let proving_key = keys::read_pk(
+        &mut BufReader::new(File::open(dir.join("pk.bin"))?),
+        RECEIPT_K,
+        &shape,
+    )?;
+
+    let now_fe = PastaFp::from(now);
+    let circuit = ReceiptCircuit {
+        timestamp_num_bits: shape.timestamp_num_bits,
+        amount_num_bits: shape.amount_num_bits,
+        scheme: shape.scheme,
+        ..receipt.to_circuit(
+            pk,
+            signature,
+            now_fe,
+            nullifier_secret,
+            &registry.path(signed.receipt.b_index),
+        )
+    };
+    let public_inputs = receipt.public_inputs(pk, now_fe, nullifier_secret, registry.root());
+    let proof = prove_receipt(&params, &proving_key, circuit, &public_inputs, OsRng)?;
+
+    fs::write(args.path("proof")?, proof)?;
+    write_json(
+        &args.path("public-inputs")?,
+        &PublicInputsJson {
+            b_public_key: signed.b_public_key,
+            a_index: signed.receipt.a_index,
+            b_index: signed.receipt.b_index,
+            amount: signed.receipt.amount,
+            now,
+            nullifier: encode(&public_inputs.nullifier),
+            registry_root: encode(&public_inputs.registry_root),
+        },
+    )
+}
+
+fn cmd_verify(args: &Args) -> Result<bool, Box<dyn Error>> {
+    let dir = args.path("keys")?;
+    let public_inputs: PublicInputsJson = read_json(&args.path("public-inputs")?)?;
+    let proof = fs::read(args.path("proof")?)?;
+
+    let params = keys::read_params(
+        &mut BufReader::new(File::open(dir.join("params.bin"))?),
+        RECEIPT_K,
+    )?;
+    let vk = keys::read_vk(
+        &mut BufReader::new(File::open(dir.join("vk.bin"))?),
+        RECEIPT_K,
+        &circuit_shape(),
+    )?;
+
+    Ok(verify_receipt(
+        &params,
+        &vk,
+        &public_inputs.to_public_inputs()?,
+        &proof,
+    ))
+}
+
+fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let (command, rest) = args.split_first().ok_or(USAGE)?;
+    let rest = Args::parse(rest)?;
+
+    match command.as_str() {
+        "keygen" => cmd_keygen(&rest),
+        "commit" => cmd_commit(&rest),
+        "sign" => cmd_sign(&rest),
+        "prove" => cmd_prove(&rest),
+        "verify" => {
+            if cmd_verify(&rest)? {
+                println!("valid");
+                Ok(())
+            } else {
+                Err("invalid proof".into())
+            }
+        }
+        _ => Err(USAGE.into()),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,405 @@
+//! Registry of accounts as a Poseidon Merkle tree.
+//!
+//! The leaf at position `index` is
+//! `Poseidon(Domain::RegistryLeaf, index, secret_commitment, x limbs, y limbs)`
+//! of the account's public key, where `secret_commitment` is
+//! `Poseidon(Domain::SecretCommitment, nullifier_secret)` of the account's
+//! nullifier secret (see [`secret_commitment`]). Inner nodes are
+//! `Poseidon(Domain::MerkleNode, left, right)`. Keys of schemes encoded in fewer native cells than
+//! ECDSA limbs, e.g. Schnorr over Pallas, are padded with zeros. Empty leaves
+//! are zero. Position bits of a leaf are derived from `index` itself, so a
+//! membership proof binds the public key and the nullifier secret to the
+//! account index.
+
+use halo2::{
+    arithmetic::{CurveAffine, FieldExt},
+    circuit::Value,
+    plonk::Error,
+};
+use maingate::{AssignedValue, MainGate, MainGateConfig, MainGateInstructions, RegionCtx};
+
+use crate::{
+    ecdsa::Ecdsa,
+    poseidon::{self, Domain, P128Pow5T3, PoseidonChip, Spec, P128_RATE, P128_WIDTH},
+    signature::ReceiptSignatureScheme,
+    NUMBER_OF_LIMBS,
+};
+
+/// Number of field elements hashed into a leaf, including the domain tag.
+const LEAF_LEN: usize = 3 + 2 * NUMBER_OF_LIMBS;
+
+fn hash_node<F: FieldExt>(left: F, right: F) -> F
+where
+    P128Pow5T3: Spec<F, P128_WIDTH, P128_RATE>,
+{
+    poseidon::hash::<F, P128Pow5T3, P128_WIDTH, P128_RATE, 3>([
+        Domain::MerkleNode.tag(),
+        left,
+        right,
+    ])
+}
+
+/// Commitment `Poseidon(Domain::SecretCommitment, nullifier_secret)`
+/// registered with an account, which fixes the secret its receipts are
+/// redeemed with.
+pub fn secret_commitment<F: FieldExt>(nullifier_secret: F) -> F
+where
+    P128Pow5T3: Spec<F, P128_WIDTH, P128_RATE>,
+{
+    poseidon::hash::<F, P128Pow5T3, P128_WIDTH, P128_RATE, 2>([
+        Domain::SecretCommitment.tag(),
+        nullifier_secret,
+    ])
+}
+
+/// Leaf of account `index` with ECDSA public key `public_key` and nullifier
+/// secret committed to in `secret_commitment`.
+pub fn registry_leaf<C: CurveAffine, F: FieldExt>(
+    index: F,
+    public_key: C,
+    secret_commitment: F,
+) -> F
+where
+    P128Pow5T3: Spec<F, P128_WIDTH, P128_RATE>,
+{
+    scheme_registry_leaf::<Ecdsa<C>, F>(index, &public_key, secret_commitment)
+}
+
+/// Leaf of account `index` with public key `public_key` of scheme `S` and
+/// nullifier secret committed to in `secret_commitment`.
+pub fn scheme_registry_leaf<S: ReceiptSignatureScheme<F>, F: FieldExt>(
+    index: F,
+    public_key: &S::PublicKey,
+    secret_commitment: F,
+) -> F
+where
+    P128Pow5T3: Spec<F, P128_WIDTH, P128_RATE>,
+{
+    let public_key = S::public_key_values(public_key);
+    assert!(public_key.len() + 3 <= LEAF_LEN);
+
+    let mut message = vec![Domain::RegistryLeaf.tag(), index, secret_commitment];
+    message.extend(public_key);
+    message.resize(LEAF_LEN, F::zero());
+    let message: [F; LEAF_LEN] = message.try_into().unwrap();
+    poseidon::hash::<F, P128Pow5T3, P128_WIDTH, P128_RATE, LEAF_LEN>(message)
+}
+
+/// Root of the tree containing `leaf` at `index`, given the siblings of the
+/// nodes on its path from the bottom up.
+pub fn compute_root<F: FieldExt>(leaf: F, index: u64, siblings: &[F]) -> F
+where
+    P128Pow5T3: Spec<F, P128_WIDTH, P128_RATE>,
+{
+    siblings
+        .iter()
+        .enumerate()
+        .fold(leaf, |node, (level, sibling)| {
+            if (index >> level) & 1 == 1 {
+                hash_node(*sibling, node)
+            } else {
+                hash_node(node, *sibling)
+            }
+        })
+}
+
+/// Append-only Merkle tree of fixed depth.
+#[derive(Clone, Debug)]
+pub struct IncrementalMerkleTree<F: FieldExt> {
+    depth: usize,
+    /// `levels[0]` are the leaves and `levels[depth]` the root. Nodes that
+    /// are missing from a level are empty subtrees.
+    levels: Vec<Vec<F>>,
+    /// `empty_roots[i]` is the root of an empty subtree of height `i`.
+    empty_roots: Vec<F>,
+}
+
+impl<F: FieldExt> IncrementalMerkleTree<F>
+where
+    P128Pow5T3: Spec<F, P128_WIDTH, P128_RATE>,
+{
+    pub fn new(depth: usize) -> Self {
+        assert!(depth < 64);
+
+        let mut empty_roots = vec![F::zero()];
+        for i in 0..depth {
+            empty_roots.push(hash_node(empty_roots[i], empty_roots[i]));
+        }
+
+        Self {
+            depth,
+            levels: vec![vec![]; depth + 1],
+            empty_roots,
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn node(&self, level: usize, index: usize) -> F {
+        self.levels[level]
+            .get(index)
+            .copied()
+            .unwrap_or(self.empty_roots[level])
+    }
+
+    /// Appends `leaf`, returning its index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tree is full.
+    pub fn insert(&mut self, leaf: F) -> u64 {
+        let index = self.len();
+        assert!(index < 1 << self.depth, "tree is full");
+
+        self.levels[0].push(leaf);
+        let mut node_index = index;
+        for level in 0..self.depth {
+            let parent_index = node_index / 2;
+            let parent = hash_node(
+                self.node(level, 2 * parent_index),
+                self.node(level, 2 * parent_index + 1),
+            );
+
+            let parents = &mut self.levels[level + 1];
+            if parent_index < parents.len() {
+                parents[parent_index] = parent;
+            } else {
+                parents.push(parent);
+            }
+            node_index = parent_index;
+        }
+
+        index as u64
+    }
+
+    pub fn root(&self) -> F {
+        self.node(self.depth, 0)
+    }
+
+    /// Siblings on the path of the leaf at `index`, from the bottom up.
+    pub fn path(&self, index: u64) -> Vec<F> {
+        assert!((index as usize) < self.len());
+
+        (0..self.depth)
+            .map(|level| self.node(level, ((index >> level) ^ 1) as usize))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MerkleChip<F: FieldExt> {
+    main_gate: MainGate<F>,
+    poseidon_chip: PoseidonChip<F, P128Pow5T3, P128_WIDTH, P128_RATE>,
+}
+
+impl<F: FieldExt> MerkleChip<F>
+where
+    P128Pow5T3: Spec<F, P128_WIDTH, P128_RATE>,
+{
+    pub fn new(main_gate_config: MainGateConfig) -> Self {
+        Self {
+            main_gate: MainGate::new(main_gate_config.clone()),
+            poseidon_chip: PoseidonChip::new(main_gate_config),
+        }
+    }
+
+    /// Commitment to `nullifier_secret`, see [`secret_commitment`].
+    pub fn secret_commitment(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        nullifier_secret: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        let tag = self
+            .poseidon_chip
+            .domain_tag(ctx, Domain::SecretCommitment)?;
+        self.poseidon_chip
+            .hash(ctx, &[tag, nullifier_secret.clone()])
+    }
+
+    /// Leaf of account `index` with public key encoded in the native cells
+    /// `public_key`, see [`ReceiptSignatureScheme::public_key_cells`], and
+    /// nullifier secret committed to in `secret_commitment`.
+    pub fn registry_leaf(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        index: &AssignedValue<F>,
+        public_key: &[AssignedValue<F>],
+        secret_commitment: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        assert!(public_key.len() + 3 <= LEAF_LEN);
+
+        let tag = self.poseidon_chip.domain_tag(ctx, Domain::RegistryLeaf)?;
+        let mut message = vec![tag, index.clone(), secret_commitment.clone()];
+        message.extend(public_key.iter().cloned());
+        while message.len() < LEAF_LEN {
+            message.push(self.main_gate.assign_constant(ctx, F::zero())?);
+        }
+        let message: [AssignedValue<F>; LEAF_LEN] = message.try_into().unwrap();
+        self.poseidon_chip.hash(ctx, &message)
+    }
+
+    /// Root of the tree containing `leaf` at `index`, given the siblings of
+    /// the nodes on its path from the bottom up.
+    ///
+    /// `index` is constrained to `siblings.len()` bits.
+    pub fn compute_root(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        leaf: &AssignedValue<F>,
+        index: &AssignedValue<F>,
+        siblings: &[Value<F>],
+    ) -> Result<AssignedValue<F>, Error> {
+        let main_gate = &self.main_gate;
+
+        // little-endian, bit `i` is set if the node at level `i` is a right child
+        let position_bits = main_gate.to_bits(ctx, index, siblings.len())?;
+
+        let mut node = leaf.clone();
+        for (sibling, is_right) in siblings.iter().zip(position_bits.iter()) {
+            let sibling = main_gate.assign_value(ctx, *sibling)?;
+            let left = main_gate.select(ctx, &sibling, &node, is_right)?;
+            let right = main_gate.select(ctx, &node, &sibling, is_right)?;
+            let tag = self.poseidon_chip.domain_tag(ctx, Domain::MerkleNode)?;
+            node = self.poseidon_chip.hash(ctx, &[tag, left, right])?;
+        }
+
+        Ok(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2::{
+        arithmetic::{Field, FieldExt},
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        halo2curves::pasta::Fp as PastaFp,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use maingate::{MainGate, MainGateConfig, MainGateInstructions, RegionCtx};
+    use rand_core::OsRng;
+
+    use super::{compute_root, IncrementalMerkleTree, MerkleChip};
+    use crate::poseidon::{P128Pow5T3, Spec, P128_RATE, P128_WIDTH};
+
+    #[test]
+    fn test_incremental_merkle_tree() {
+        let depth = 4;
+        let mut tree = IncrementalMerkleTree::<PastaFp>::new(depth);
+        let empty_root = tree.root();
+
+        let leaves: Vec<PastaFp> = (0..5).map(|_| PastaFp::random(OsRng)).collect();
+        for (i, leaf) in leaves.iter().enumerate() {
+            assert_eq!(tree.insert(*leaf), i as u64);
+        }
+        assert_ne!(tree.root(), empty_root);
+
+        // root of the tree built from all leaves at once
+        let mut level: Vec<PastaFp> = leaves.clone();
+        level.resize(1 << depth, PastaFp::zero());
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| super::hash_node(pair[0], pair[1]))
+                .collect();
+        }
+        assert_eq!(tree.root(), level[0]);
+
+        for (i, leaf) in leaves.iter().enumerate() {
+            let path = tree.path(i as u64);
+            assert_eq!(path.len(), depth);
+            assert_eq!(compute_root(*leaf, i as u64, &path), tree.root());
+            assert_ne!(compute_root(*leaf, (i as u64) ^ 1, &path), tree.root());
+        }
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct MerkleCircuit<F: FieldExt> {
+        leaf: Value<F>,
+        index: Value<F>,
+        siblings: Vec<Value<F>>,
+        root: Value<F>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for MerkleCircuit<F>
+    where
+        P128Pow5T3: Spec<F, P128_WIDTH, P128_RATE>,
+    {
+        type Config = MainGateConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                siblings: vec![Value::unknown(); self.siblings.len()],
+                ..Default::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            MainGate::<F>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let main_gate = MainGate::<F>::new(config.clone());
+            let merkle_chip = MerkleChip::new(config);
+
+            layouter.assign_region(
+                || "merkle",
+                |mut region| {
+                    let offset = &mut 0;
+                    let ctx = &mut RegionCtx::new(&mut region, offset);
+
+                    let leaf = main_gate.assign_value(ctx, self.leaf)?;
+                    let index = main_gate.assign_value(ctx, self.index)?;
+                    let root = merkle_chip.compute_root(ctx, &leaf, &index, &self.siblings)?;
+                    let expected = main_gate.assign_value(ctx, self.root)?;
+                    main_gate.assert_equal(ctx, &root, &expected)
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn test_merkle_chip() {
+        let depth = 4;
+        let mut tree = IncrementalMerkleTree::<PastaFp>::new(depth);
+        let leaves: Vec<PastaFp> = (0..6).map(|_| PastaFp::random(OsRng)).collect();
+        for leaf in leaves.iter() {
+            tree.insert(*leaf);
+        }
+
+        let run = |leaf: PastaFp, index: u64, siblings: &[PastaFp]| {
+            let circuit = MerkleCircuit {
+                leaf: Value::known(leaf),
+                index: Value::known(PastaFp::from(index)),
+                siblings: siblings.iter().map(|s| Value::known(*s)).collect(),
+                root: Value::known(tree.root()),
+            };
+            let prover = MockProver::run(14, &circuit, vec![vec![]]).unwrap();
+            prover.verify()
+        };
+
+        let path = tree.path(5);
+        assert_eq!(run(leaves[5], 5, &path), Ok(()));
+        // wrong position
+        assert!(run(leaves[5], 4, &path).is_err());
+        // index doesn't fit in the depth of the tree
+        assert!(run(leaves[5], 5 + (1 << depth), &path).is_err());
+        // not a member
+        assert!(run(PastaFp::random(OsRng), 5, &path).is_err());
+    }
+}
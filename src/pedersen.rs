@@ -0,0 +1,365 @@
+//! Receipts with amounts hidden in Pedersen commitments over Pallas.
+//!
+//! The commitment to `amount` with blinding factor `r` is
+//! `C = [amount] G + [r] H`, where `G` is the Pallas generator and `H` is
+//! derived from Poseidon outputs so that nobody knows its discrete logarithm
+//! to the base `G`. Coordinates of `C` are native to circuits over
+//! `pallas::Base`, so [`HiddenAmountReceiptCircuit`] computes it with the
+//! native arithmetic of [`SchnorrChip`] whatever the signature scheme.
+//!
+//! Blinding factors are restricted to be below $2^{254}$ like Schnorr
+//! scalars, which keeps their bit decompositions unique. [`random_blinding`]
+//! samples them.
+
+use ff::PrimeFieldBits;
+use halo2::{
+    arithmetic::{CurveAffine, Field},
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    halo2curves::{
+        group::Curve,
+        pasta::{pallas, Fp, Fq},
+    },
+    plonk::{Circuit, ConstraintSystem, Error},
+};
+use maingate::{MainGate, MainGateInstructions, RegionCtx};
+use rand::rngs::OsRng;
+
+use crate::{
+    convert_field,
+    poseidon::{self, Domain, P128Pow5T3, P128_RATE, P128_WIDTH},
+    schnorr::{SchnorrChip, SCALAR_NUM_BITS},
+    signature::ReceiptSignatureScheme,
+    ReceiptCircuit, ReceiptConfig, ReceiptPublicInputs,
+};
+
+/// Bit width of committed amounts.
+pub const HIDDEN_AMOUNT_NUM_BITS: usize = 64;
+
+/// Base `H` of the blinding factor.
+pub fn blinding_base() -> pallas::Affine {
+    (0u64..)
+        .find_map(|i| {
+            let x = poseidon::hash::<Fp, P128Pow5T3, P128_WIDTH, P128_RATE, 2>([
+                Domain::PedersenBlindingBase.tag(),
+                Fp::from(i),
+            ]);
+            let y = Option::<Fp>::from((x.square() * x + Fp::from(5)).sqrt())?;
+            Option::from(pallas::Affine::from_xy(x, y))
+        })
+        .unwrap()
+}
+
+/// Random blinding factor below $2^{254}$.
+pub fn random_blinding() -> Fq {
+    loop {
+        let blinding = Fq::random(OsRng);
+        if blinding
+            .to_le_bits()
+            .iter()
+            .skip(SCALAR_NUM_BITS)
+            .all(|bit| !*bit)
+        {
+            return blinding;
+        }
+    }
+}
+
+/// Pedersen commitment to `amount` with blinding factor `blinding`.
+pub fn commit(amount: Fp, blinding: Fq) -> pallas::Affine {
+    let g = pallas::Affine::generator();
+    (g * convert_field::<Fp, Fq>(amount) + blinding_base() * blinding).to_affine()
+}
+
+/// [`ReceiptCircuit`] exposing a Pedersen commitment to `amount` instead of
+/// `amount` itself.
+///
+/// The committed amount is the one of the signed receipt and is constrained
+/// to `receipt.amount_num_bits` bits by the running-sum range check of
+/// `receipt`. Synthesis fails if `amount_num_bits` exceeds
+/// [`HIDDEN_AMOUNT_NUM_BITS`], the width of the bit decomposition feeding the
+/// fixed-base scalar multiplication. `amount_blinding` must be below
+/// $2^{254}$.
+///
+/// Public inputs are described by [`HiddenAmountPublicInputs`].
+#[derive(Clone, Debug, Default)]
+pub struct HiddenAmountReceiptCircuit<S: ReceiptSignatureScheme<Fp>> {
+    pub receipt: ReceiptCircuit<S, Fp>,
+    pub amount_blinding: Value<Fq>,
+}
+
+impl<S: ReceiptSignatureScheme<Fp>> HiddenAmountReceiptCircuit<S> {
+    /// Parameters that change the layout of the circuit, and hence its keys,
+    /// without changing its constraint system.
+    pub fn shape_parameters(&self) -> Vec<u64> {
+        self.receipt.shape_parameters()
+    }
+}
+
+impl<S: ReceiptSignatureScheme<Fp>> Circuit<Fp> for HiddenAmountReceiptCircuit<S> {
+    type Config = ReceiptConfig<Fp, S>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self {
+            receipt: self.receipt.without_witnesses(),
+            amount_blinding: Value::unknown(),
+        }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+        ReceiptConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fp>,
+    ) -> Result<(), Error> {
+        if self.receipt.amount_num_bits > HIDDEN_AMOUNT_NUM_BITS {
+            return Err(Error::Synthesis);
+        }
+
+        let main_gate = MainGate::<Fp>::new(config.main_gate_config.clone());
+        let pallas_chip = SchnorrChip::new(config.main_gate_config.clone());
+
+        let receipt = self.receipt.verify_receipt(&config, &mut layouter)?;
+
+        let commitment = layouter.assign_region(
+            || "commit to amount",
+            |mut region| {
+                let offset = &mut 0;
+                let ctx = &mut RegionCtx::new(&mut region, offset);
+
+                let amount_bits =
+                    main_gate.to_bits(ctx, &receipt.amount, HIDDEN_AMOUNT_NUM_BITS)?;
+                let blinding = self.amount_blinding.map(convert_field::<Fq, Fp>);
+                let blinding = main_gate.assign_value(ctx, blinding)?;
+                let blinding_bits = main_gate.to_bits(ctx, &blinding, SCALAR_NUM_BITS)?;
+
+                pallas_chip.fixed_base_msm(
+                    ctx,
+                    &[
+                        (pallas::Affine::generator(), amount_bits.as_slice()),
+                        (blinding_base(), blinding_bits.as_slice()),
+                    ],
+                )
+            },
+        )?;
+
+        let public_inputs = receipt.public_inputs(
+            &[&commitment.x, &commitment.y],
+            self.receipt.expose_expires_by,
+        );
+        config.expose_public_inputs(&mut layouter, &public_inputs)?;
+
+        S::load_tables(&config.signature_config, &mut layouter)
+    }
+}
+
+/// Public inputs of [`HiddenAmountReceiptCircuit`].
+///
+/// The instance column is laid out as the one of [`ReceiptPublicInputs`],
+/// with the `x` and `y` coordinates of `amount_commitment` in place of
+/// `amount`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HiddenAmountPublicInputs<S: ReceiptSignatureScheme<Fp>> {
+    pub b_public_key: S::PublicKey,
+    pub a_index: Fp,
+    pub b_index: Fp,
+    /// See [`commit`].
+    pub amount_commitment: pallas::Affine,
+    pub now: Fp,
+    pub nullifier: Fp,
+    pub registry_root: Fp,
+    pub expires_by: Option<Fp>,
+    pub a_public_key: Option<S::PublicKey>,
+}
+
+impl<S: ReceiptSignatureScheme<Fp>> HiddenAmountPublicInputs<S> {
+    /// Public inputs of the receipt with public inputs `public_inputs`, once
+    /// its amount is committed to with `amount_blinding`.
+    pub fn from_public_inputs(
+        public_inputs: &ReceiptPublicInputs<S, Fp>,
+        amount_blinding: Fq,
+    ) -> Self {
+        Self {
+            b_public_key: public_inputs.b_public_key,
+            a_index: public_inputs.a_index,
+            b_index: public_inputs.b_index,
+            amount_commitment: commit(public_inputs.amount, amount_blinding),
+            now: public_inputs.now,
+            nullifier: public_inputs.nullifier,
+            registry_root: public_inputs.registry_root,
+            expires_by: public_inputs.expires_by,
+            a_public_key: public_inputs.a_public_key,
+        }
+    }
+
+    /// Values of the receipt instance column.
+    pub fn to_instance(&self) -> Vec<Fp> {
+        let amount_commitment = self.amount_commitment.coordinates().unwrap();
+
+        let mut instance = S::public_key_values(&self.b_public_key);
+        instance.push(self.a_index);
+        instance.push(self.b_index);
+        instance.push(*amount_commitment.x());
+        instance.push(*amount_commitment.y());
+        instance.push(self.now);
+        instance.push(self.nullifier);
+        instance.push(self.registry_root);
+        instance.extend(self.expires_by);
+        if let Some(a_public_key) = &self.a_public_key {
+            instance.extend(S::public_key_values(a_public_key));
+        }
+        instance
+    }
+
+    /// Values of every instance column of the circuit, see
+    /// [`ReceiptPublicInputs::instances`].
+    pub fn instances(&self) -> Vec<Vec<Fp>> {
+        vec![vec![], self.to_instance()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::PrimeFieldBits;
+    use halo2::{
+        arithmetic::{Field, FieldExt},
+        circuit::Value,
+        dev::MockProver,
+        halo2curves::{
+            group::Curve,
+            pasta::{pallas, Fp, Fq},
+        },
+    };
+    use rand_core::OsRng;
+
+    use super::{
+        commit, random_blinding, HiddenAmountPublicInputs, HiddenAmountReceiptCircuit,
+        HIDDEN_AMOUNT_NUM_BITS,
+    };
+    use crate::{
+        merkle::{scheme_registry_leaf, secret_commitment, IncrementalMerkleTree},
+        schnorr::{self, Schnorr, SCALAR_NUM_BITS},
+        Receipt, ReceiptCircuit,
+    };
+
+    #[test]
+    fn test_commit() {
+        let blinding = random_blinding();
+        assert!(blinding
+            .to_le_bits()
+            .iter()
+            .skip(SCALAR_NUM_BITS)
+            .all(|bit| !*bit));
+
+        let amount = Fp::from(100);
+        assert_eq!(commit(amount, blinding), commit(amount, blinding));
+        assert_ne!(
+            commit(amount, blinding),
+            commit(amount + Fp::one(), blinding)
+        );
+        assert_ne!(commit(amount, blinding), commit(amount, random_blinding()));
+        // homomorphic in both the amount and the blinding factor
+        let other = random_blinding();
+        assert_eq!(
+            (commit(amount, blinding) + commit(Fp::from(50), other)).to_affine(),
+            commit(Fp::from(150), blinding + other)
+        );
+    }
+
+    #[test]
+    fn test_hidden_amount_receipt() {
+        let g = pallas::Affine::generator();
+        let sk = Fq::random(OsRng);
+        let pk = (g * sk).to_affine();
+        let now = Fp::from(1_600_000_000);
+        let nullifier_secret = Fp::random(OsRng);
+
+        let receipt = Receipt {
+            a_index: Fp::from(1),
+            b_index: Fp::from(2),
+            amount: Fp::from(100),
+            expires_by: Fp::from(1_700_000_000),
+        };
+
+        let mut registry = IncrementalMerkleTree::new(8);
+        registry.insert(Fp::random(OsRng));
+        registry.insert(Fp::random(OsRng));
+        registry.insert(scheme_registry_leaf::<Schnorr, _>(
+            receipt.b_index,
+            &pk,
+            secret_commitment(nullifier_secret),
+        ));
+        let path = registry.path(2);
+        let root = registry.root();
+
+        let blinding = random_blinding();
+        let circuit = HiddenAmountReceiptCircuit {
+            receipt: receipt.to_circuit_with(
+                Schnorr,
+                pk,
+                schnorr::sign(sk, receipt.hash()),
+                now,
+                nullifier_secret,
+                &path,
+            ),
+            amount_blinding: Value::known(blinding),
+        };
+        let public_inputs = HiddenAmountPublicInputs::from_public_inputs(
+            &receipt.public_inputs_with::<Schnorr>(pk, now, nullifier_secret, root),
+            blinding,
+        );
+
+        let k = 16;
+        let prover = MockProver::run(k, &circuit, public_inputs.instances()).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        // commitment to another amount, or with another blinding factor
+        for amount_commitment in [
+            commit(receipt.amount + Fp::one(), blinding),
+            commit(receipt.amount, random_blinding()),
+        ] {
+            let tampered_inputs = HiddenAmountPublicInputs {
+                amount_commitment,
+                ..public_inputs
+            };
+            let prover = MockProver::run(k, &circuit, tampered_inputs.instances()).unwrap();
+            assert!(prover.verify().is_err());
+        }
+
+        // amount that doesn't fit in 64 bits, even if signed
+        let receipt = Receipt {
+            amount: Fp::from_u128(1 << HIDDEN_AMOUNT_NUM_BITS),
+            ..receipt
+        };
+        let wrapped = HiddenAmountReceiptCircuit {
+            receipt: receipt.to_circuit_with(
+                Schnorr,
+                pk,
+                schnorr::sign(sk, receipt.hash()),
+                now,
+                nullifier_secret,
+                &path,
+            ),
+            amount_blinding: Value::known(blinding),
+        };
+        let public_inputs = HiddenAmountPublicInputs::from_public_inputs(
+            &receipt.public_inputs_with::<Schnorr>(pk, now, nullifier_secret, root),
+            blinding,
+        );
+        let prover = MockProver::run(k, &wrapped, public_inputs.instances()).unwrap();
+        assert!(prover.verify().is_err());
+
+        // range checks wider than the committed bits aren't synthesized
+        let wide = HiddenAmountReceiptCircuit {
+            receipt: ReceiptCircuit {
+                amount_num_bits: HIDDEN_AMOUNT_NUM_BITS + 1,
+                ..wrapped.receipt.clone()
+            },
+            ..wrapped
+        };
+        assert!(MockProver::run(k, &wide, public_inputs.instances()).is_err());
+    }
+}
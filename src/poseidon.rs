@@ -0,0 +1,292 @@
+//! Poseidon sponge over the native field, laid out with `MainGate`.
+//!
+//! Round constants and MDS matrices are taken from a `halo2_gadgets` Poseidon
+//! [`Spec`], so the in-circuit hash matches [`hash`] computed off-circuit with
+//! the same spec. Only the `ConstantLength` domain is supported: the capacity
+//! element is initialised to $L \cdot 2^{64}$ which separates messages of
+//! different lengths only. Every message hashed by the crate starts with the
+//! tag of its [`Domain`], which separates its uses.
+//!
+//! The chip hard-codes the $x^5$ S-box, so `S` must be a `Pow5` spec
+//! (e.g. [`P128Pow5T3`]).
+
+use std::marker::PhantomData;
+
+use halo2::{arithmetic::FieldExt, plonk::Error};
+use halo2_gadgets::poseidon::primitives::{ConstantLength, Hash};
+pub use halo2_gadgets::poseidon::primitives::{Mds, P128Pow5T3, Spec};
+use maingate::{AssignedValue, MainGate, MainGateConfig, MainGateInstructions, RegionCtx, Term};
+
+/// State width of [`P128Pow5T3`].
+pub const P128_WIDTH: usize = 3;
+/// Rate of [`P128Pow5T3`].
+pub const P128_RATE: usize = 2;
+
+/// Uses of Poseidon in the crate. The tag of each is absorbed as the first
+/// element of its messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Domain {
+    /// `Receipt::hash`.
+    Receipt = 1,
+    /// `Receipt::nullifier`.
+    Nullifier = 2,
+    /// Inner nodes of the registry tree.
+    MerkleNode = 3,
+    /// Leaves of the registry tree.
+    RegistryLeaf = 4,
+    /// Commitments to nullifier secrets.
+    SecretCommitment = 5,
+    /// Links of the chain committing to a batch of receipts.
+    ReceiptBatch = 6,
+    /// Links of the Fiat-Shamir chain of batched ECDSA verification.
+    EcdsaBatchChallenge = 7,
+    /// Schnorr challenges.
+    SchnorrChallenge = 8,
+    /// Candidates for the auxiliary point of `SchnorrChip`.
+    SchnorrAuxGenerator = 9,
+    /// Candidates for the blinding base of Pedersen commitments.
+    PedersenBlindingBase = 10,
+}
+
+impl Domain {
+    pub fn tag<F: FieldExt>(self) -> F {
+        F::from(self as u64)
+    }
+}
+
+/// Hashes `message` off-circuit with the `ConstantLength<L>` domain.
+pub fn hash<F: FieldExt, S: Spec<F, T, RATE>, const T: usize, const RATE: usize, const L: usize>(
+    message: [F; L],
+) -> F {
+    Hash::<F, S, ConstantLength<L>, T, RATE>::init().hash(message)
+}
+
+#[derive(Clone, Debug)]
+pub struct PoseidonChip<F: FieldExt, S: Spec<F, T, RATE>, const T: usize, const RATE: usize> {
+    main_gate: MainGate<F>,
+    round_constants: Vec<[F; T]>,
+    mds: Mds<F, T>,
+    _marker: PhantomData<S>,
+}
+
+impl<F: FieldExt, S: Spec<F, T, RATE>, const T: usize, const RATE: usize>
+    PoseidonChip<F, S, T, RATE>
+{
+    pub fn new(main_gate_config: MainGateConfig) -> Self {
+        let (round_constants, mds, _) = S::constants();
+        Self {
+            main_gate: MainGate::new(main_gate_config),
+            round_constants,
+            mds,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Assigns the tag of `domain` as a constant.
+    pub fn domain_tag(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        domain: Domain,
+    ) -> Result<AssignedValue<F>, Error> {
+        self.main_gate.assign_constant(ctx, domain.tag())
+    }
+
+    /// Hashes `message` with the `ConstantLength<L>` domain.
+    ///
+    /// Message is absorbed `RATE` elements at a time, padded with zeros to a
+    /// multiple of `RATE`, and the first element of the final state is
+    /// squeezed.
+    pub fn hash<const L: usize>(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        message: &[AssignedValue<F>; L],
+    ) -> Result<AssignedValue<F>, Error> {
+        let main_gate = &self.main_gate;
+
+        let initial_capacity_element = F::from_u128((L as u128) << 64);
+        let mut state = (0..T)
+            .map(|i| {
+                if i == RATE {
+                    main_gate.assign_constant(ctx, initial_capacity_element)
+                } else {
+                    main_gate.assign_constant(ctx, F::zero())
+                }
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let num_chunks = (L + RATE - 1) / RATE;
+        let padded_message: Vec<Option<&AssignedValue<F>>> = message
+            .iter()
+            .map(Some)
+            .chain(std::iter::repeat(None))
+            .take(num_chunks * RATE)
+            .collect();
+
+        for chunk in padded_message.chunks(RATE) {
+            for (word, input) in state.iter_mut().zip(chunk.iter()) {
+                // padding is zero, so it leaves the state unchanged
+                if let Some(input) = input {
+                    *word = main_gate.add(ctx, word, input)?;
+                }
+            }
+            state = self.permute(ctx, &state)?;
+        }
+
+        Ok(state[0].clone())
+    }
+
+    /// Poseidon permutation with `R_F / 2` full rounds, `R_P` partial rounds
+    /// and `R_F / 2` full rounds.
+    fn permute(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        state: &[AssignedValue<F>],
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        let r_f = S::full_rounds() / 2;
+        let r_p = S::partial_rounds();
+
+        let mut state = state.to_vec();
+        for (round, round_constants) in self.round_constants.iter().enumerate() {
+            let is_full_round = round < r_f || round >= r_f + r_p;
+            state = state
+                .iter()
+                .zip(round_constants.iter())
+                .enumerate()
+                .map(|(i, (word, rc))| {
+                    let word = self.main_gate.add_constant(ctx, word, *rc)?;
+                    if is_full_round || i == 0 {
+                        self.sbox(ctx, &word)
+                    } else {
+                        Ok(word)
+                    }
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            state = self.apply_mds(ctx, &state)?;
+        }
+
+        Ok(state)
+    }
+
+    /// x^5
+    fn sbox(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        x: &AssignedValue<F>,
+    ) -> Result<AssignedValue<F>, Error> {
+        let x2 = self.main_gate.mul(ctx, x, x)?;
+        let x4 = self.main_gate.mul(ctx, &x2, &x2)?;
+        self.main_gate.mul(ctx, &x4, x)
+    }
+
+    fn apply_mds(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        state: &[AssignedValue<F>],
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        self.mds
+            .iter()
+            .map(|row| {
+                let terms: Vec<Term<F>> = state
+                    .iter()
+                    .zip(row.iter())
+                    .map(|(word, coeff)| Term::Assigned(word, *coeff))
+                    .collect();
+                self.main_gate.compose(ctx, &terms, F::zero())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2::{
+        arithmetic::{Field, FieldExt},
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        halo2curves::pasta::Fp as PastaFp,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use maingate::{MainGate, MainGateConfig, MainGateInstructions, RegionCtx};
+    use rand_core::OsRng;
+
+    use super::{hash, P128Pow5T3, PoseidonChip, Spec, P128_RATE, P128_WIDTH};
+
+    #[derive(Clone, Debug)]
+    struct PoseidonCircuit<F: FieldExt, const L: usize> {
+        message: [Value<F>; L],
+        expected: Value<F>,
+    }
+
+    impl<F: FieldExt, const L: usize> Circuit<F> for PoseidonCircuit<F, L>
+    where
+        P128Pow5T3: Spec<F, P128_WIDTH, P128_RATE>,
+    {
+        type Config = MainGateConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                message: [Value::unknown(); L],
+                expected: Value::unknown(),
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            MainGate::<F>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let main_gate = MainGate::<F>::new(config.clone());
+            let poseidon_chip = PoseidonChip::<F, P128Pow5T3, P128_WIDTH, P128_RATE>::new(config);
+
+            layouter.assign_region(
+                || "poseidon",
+                |mut region| {
+                    let offset = &mut 0;
+                    let ctx = &mut RegionCtx::new(&mut region, offset);
+
+                    let mut message = vec![];
+                    for word in self.message.iter() {
+                        message.push(main_gate.assign_value(ctx, *word)?);
+                    }
+                    let message: [_; L] = message.try_into().unwrap();
+
+                    let digest = poseidon_chip.hash(ctx, &message)?;
+                    let expected = main_gate.assign_value(ctx, self.expected)?;
+                    main_gate.assert_equal(ctx, &digest, &expected)
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn test_poseidon() {
+        fn run<const L: usize>() {
+            let message = [(); L].map(|_| PastaFp::random(OsRng));
+            let expected = hash::<PastaFp, P128Pow5T3, P128_WIDTH, P128_RATE, L>(message);
+
+            let circuit = PoseidonCircuit::<PastaFp, L> {
+                message: message.map(Value::known),
+                expected: Value::known(expected),
+            };
+            let prover = MockProver::run(12, &circuit, vec![vec![]]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+
+            let circuit = PoseidonCircuit::<PastaFp, L> {
+                message: message.map(Value::known),
+                expected: Value::known(expected + PastaFp::one()),
+            };
+            let prover = MockProver::run(12, &circuit, vec![vec![]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+
+        run::<1>();
+        run::<2>();
+        run::<4>();
+        run::<5>();
+    }
+}
@@ -0,0 +1,226 @@
+//! Proving and verification of `ReceiptCircuit` with the IPA commitment
+//! scheme over the Pasta curves.
+//!
+//! The circuit is defined over the Pallas base field, so commitments are
+//! Vesta points.
+
+use halo2::{
+    halo2curves::pasta::{EqAffine, Fp as PastaFp},
+    plonk::{
+        create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, Error, ProvingKey, VerifyingKey,
+    },
+    poly::{
+        commitment::ParamsProver,
+        ipa::{
+            commitment::{IPACommitmentScheme, ParamsIPA},
+            multiopen::{ProverIPA, VerifierIPA},
+            strategy::SingleStrategy,
+        },
+        VerificationStrategy,
+    },
+    transcript::{
+        Blake2bRead, Blake2bWrite, Challenge255, TranscriptReadBuffer, TranscriptWriterBuffer,
+    },
+};
+use rand::RngCore;
+
+use crate::{signature::ReceiptSignatureScheme, ReceiptCircuit, ReceiptPublicInputs};
+
+/// Number of rows of `ReceiptCircuit` is `2^RECEIPT_K`.
+pub const RECEIPT_K: u32 = 20;
+
+/// Generates IPA parameters for circuits of `2^k` rows.
+pub fn setup(k: u32) -> ParamsIPA<EqAffine> {
+    ParamsIPA::<EqAffine>::new(k)
+}
+
+/// Generates the proving key of `circuit`. Only the shape of `circuit` is
+/// used, witnesses may be unknown.
+pub fn keygen<S: ReceiptSignatureScheme<PastaFp>>(
+    params: &ParamsIPA<EqAffine>,
+    circuit: &ReceiptCircuit<S, PastaFp>,
+) -> Result<ProvingKey<EqAffine>, Error> {
+    let circuit = circuit.without_witnesses();
+    let vk = keygen_vk(params, &circuit)?;
+    keygen_pk(params, vk, &circuit)
+}
+
+/// Proves `circuit` with `public_inputs`, returning the serialized proof.
+pub fn prove_receipt<S: ReceiptSignatureScheme<PastaFp>>(
+    params: &ParamsIPA<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: ReceiptCircuit<S, PastaFp>,
+    public_inputs: &ReceiptPublicInputs<S, PastaFp>,
+    rng: impl RngCore,
+) -> Result<Vec<u8>, Error> {
+    let instances = public_inputs.instances();
+    let instances: Vec<&[PastaFp]> = instances.iter().map(|column| column.as_slice()).collect();
+
+    let mut transcript = Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![]);
+    create_proof::<IPACommitmentScheme<EqAffine>, ProverIPA<EqAffine>, _, _, _, _>(
+        params,
+        pk,
+        &[circuit],
+        &[&instances],
+        rng,
+        &mut transcript,
+    )?;
+
+    Ok(transcript.finalize())
+}
+
+/// Verifies `proof` against `public_inputs`.
+pub fn verify_receipt<S: ReceiptSignatureScheme<PastaFp>>(
+    params: &ParamsIPA<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    public_inputs: &ReceiptPublicInputs<S, PastaFp>,
+    proof: &[u8],
+) -> bool {
+    let instances = public_inputs.instances();
+    let instances: Vec<&[PastaFp]> = instances.iter().map(|column| column.as_slice()).collect();
+
+    let strategy = SingleStrategy::new(params);
+    let mut transcript = Blake2bRead::<_, EqAffine, Challenge255<_>>::init(proof);
+    verify_proof::<IPACommitmentScheme<EqAffine>, VerifierIPA<EqAffine>, _, _, _>(
+        params,
+        vk,
+        strategy,
+        &[&instances],
+        &mut transcript,
+    )
+    .is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2::{
+        arithmetic::{CurveAffine, Field},
+        halo2curves::{
+            group::Curve,
+            pasta::{pallas, Fp as PastaFp, Fq},
+            secp256k1::Secp256k1Affine,
+        },
+    };
+    use rand_core::OsRng;
+
+    use super::{keygen, prove_receipt, setup, verify_receipt, RECEIPT_K};
+    use crate::{
+        merkle::{registry_leaf, scheme_registry_leaf, secret_commitment, IncrementalMerkleTree},
+        schnorr::{self, Schnorr},
+        signature::ReceiptSignatureScheme,
+        Receipt, ReceiptCircuit, ReceiptPublicInputs, DEFAULT_REGISTRY_DEPTH,
+    };
+
+    fn receipt() -> Receipt<PastaFp> {
+        Receipt {
+            a_index: PastaFp::from(1),
+            b_index: PastaFp::from(2),
+            amount: PastaFp::from(100),
+            expires_by: PastaFp::from(1_700_000_000),
+        }
+    }
+
+    /// Proves `circuit` at `2^k` rows, and checks that the proof verifies
+    /// against `public_inputs` only, and that a modified proof doesn't.
+    fn assert_proof_round_trip<S: ReceiptSignatureScheme<PastaFp>>(
+        k: u32,
+        circuit: ReceiptCircuit<S, PastaFp>,
+        public_inputs: ReceiptPublicInputs<S, PastaFp>,
+    ) {
+        let params = setup(k);
+        let proving_key = keygen(&params, &circuit).unwrap();
+        let proof = prove_receipt(&params, &proving_key, circuit, &public_inputs, OsRng).unwrap();
+
+        assert!(verify_receipt(
+            &params,
+            proving_key.get_vk(),
+            &public_inputs,
+            &proof
+        ));
+
+        let tampered_inputs = ReceiptPublicInputs {
+            amount: public_inputs.amount + PastaFp::one(),
+            ..public_inputs
+        };
+        assert!(!verify_receipt(
+            &params,
+            proving_key.get_vk(),
+            &tampered_inputs,
+            &proof
+        ));
+
+        let mut tampered_proof = proof;
+        tampered_proof[0] ^= 1;
+        assert!(!verify_receipt(
+            &params,
+            proving_key.get_vk(),
+            &public_inputs,
+            &tampered_proof
+        ));
+    }
+
+    #[test]
+    #[ignore = "real proof at k = 20, run with --release"]
+    fn test_prove_receipt() {
+        let g = Secp256k1Affine::generator();
+        let sk = <Secp256k1Affine as CurveAffine>::ScalarExt::random(OsRng);
+        let pk = (g * sk).to_affine();
+        let now = PastaFp::from(1_600_000_000);
+
+        let receipt = receipt();
+        let nullifier_secret = PastaFp::random(OsRng);
+
+        let mut registry = IncrementalMerkleTree::new(DEFAULT_REGISTRY_DEPTH);
+        registry.insert(PastaFp::random(OsRng));
+        registry.insert(PastaFp::random(OsRng));
+        let b_index = registry.insert(registry_leaf(
+            receipt.b_index,
+            pk,
+            secret_commitment(nullifier_secret),
+        ));
+
+        let circuit = receipt.to_circuit(
+            pk,
+            receipt.sign::<Secp256k1Affine>(sk),
+            now,
+            nullifier_secret,
+            &registry.path(b_index),
+        );
+        let public_inputs = receipt.public_inputs(pk, now, nullifier_secret, registry.root());
+
+        assert_proof_round_trip(RECEIPT_K, circuit, public_inputs);
+    }
+
+    #[test]
+    fn test_prove_schnorr_receipt() {
+        let g = pallas::Affine::generator();
+        let sk = Fq::random(OsRng);
+        let pk = (g * sk).to_affine();
+        let now = PastaFp::from(1_600_000_000);
+
+        let receipt = receipt();
+        let nullifier_secret = PastaFp::random(OsRng);
+
+        let mut registry = IncrementalMerkleTree::new(DEFAULT_REGISTRY_DEPTH);
+        registry.insert(PastaFp::random(OsRng));
+        registry.insert(PastaFp::random(OsRng));
+        let b_index = registry.insert(scheme_registry_leaf::<Schnorr, _>(
+            receipt.b_index,
+            &pk,
+            secret_commitment(nullifier_secret),
+        ));
+
+        let circuit = receipt.to_circuit_with(
+            Schnorr,
+            pk,
+            schnorr::sign(sk, receipt.hash()),
+            now,
+            nullifier_secret,
+            &registry.path(b_index),
+        );
+        let public_inputs =
+            receipt.public_inputs_with::<Schnorr>(pk, now, nullifier_secret, registry.root());
+
+        assert_proof_round_trip(16, circuit, public_inputs);
+    }
+}
@@ -0,0 +1,83 @@
+use ff::PrimeField;
+use halo2::arithmetic::FieldExt;
+use num_bigint::BigUint;
+use num_traits::Num;
+
+use crate::{signature::ReceiptSignatureScheme, BIT_LEN_LIMB, NUMBER_OF_LIMBS};
+
+/// Splits `fe` into `NUMBER_OF_LIMBS` little-endian limbs of `BIT_LEN_LIMB`
+/// bits each, matching the limbs of an integer assigned by the `integer` chip.
+pub fn decompose_limbs<F: PrimeField, N: FieldExt>(fe: F) -> [N; NUMBER_OF_LIMBS] {
+    decompose_limbs_with(fe, NUMBER_OF_LIMBS, BIT_LEN_LIMB)
+        .try_into()
+        .unwrap()
+}
+
+/// Like [`decompose_limbs`], for `number_of_limbs` limbs of `bit_len_limb`
+/// bits.
+pub(crate) fn decompose_limbs_with<F: PrimeField, N: FieldExt>(
+    fe: F,
+    number_of_limbs: usize,
+    bit_len_limb: usize,
+) -> Vec<N> {
+    let fe = BigUint::from_bytes_le(fe.to_repr().as_ref());
+    let mask = (BigUint::from(1u64) << bit_len_limb) - 1u64;
+    (0..number_of_limbs)
+        .map(|i| {
+            let limb = (&fe >> (i * bit_len_limb)) & &mask;
+            N::from_str_vartime(&limb.to_str_radix(10)[..]).unwrap()
+        })
+        .collect()
+}
+
+/// Public inputs of `ReceiptCircuit`.
+///
+/// The instance column is laid out as
+/// `[b_public_key cells, a_index, b_index, amount, now, nullifier, registry_root, expires_by?, a_public_key cells?]`
+/// where `expires_by` is only present if the circuit exposes it and
+/// `a_public_key` only if the receipt is co-signed. The cells of a public key
+/// are given by [`ReceiptSignatureScheme::public_key_values`], i.e. the `x`
+/// and then `y` limbs of an ECDSA key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReceiptPublicInputs<S: ReceiptSignatureScheme<N>, N: FieldExt> {
+    pub b_public_key: S::PublicKey,
+    pub a_index: N,
+    pub b_index: N,
+    pub amount: N,
+    /// Time at which the receipt must not have expired yet.
+    pub now: N,
+    /// See [`crate::Receipt::nullifier`].
+    pub nullifier: N,
+    /// Root of the account registry, see [`crate::merkle`].
+    pub registry_root: N,
+    pub expires_by: Option<N>,
+    /// Payer key of a co-signed receipt, see [`crate::SignatureMode`].
+    pub a_public_key: Option<S::PublicKey>,
+}
+
+impl<S: ReceiptSignatureScheme<N>, N: FieldExt> ReceiptPublicInputs<S, N> {
+    /// Values of the receipt instance column.
+    pub fn to_instance(&self) -> Vec<N> {
+        let mut instance = S::public_key_values(&self.b_public_key);
+        instance.push(self.a_index);
+        instance.push(self.b_index);
+        instance.push(self.amount);
+        instance.push(self.now);
+        instance.push(self.nullifier);
+        instance.push(self.registry_root);
+        instance.extend(self.expires_by);
+        if let Some(a_public_key) = &self.a_public_key {
+            instance.extend(S::public_key_values(a_public_key));
+        }
+        instance
+    }
+
+    /// Values of every instance column of the circuit, in the order expected
+    /// by the prover and the verifier.
+    ///
+    /// The first instance column is the one `MainGate` configures, which the
+    /// receipt circuit leaves empty.
+    pub fn instances(&self) -> Vec<Vec<N>> {
+        vec![vec![], self.to_instance()]
+    }
+}
@@ -0,0 +1,149 @@
+//! Range check built on the running sum decomposition.
+//!
+//! A value is constrained to `num_bits` bits by decomposing it into
+//! `num_bits / K` windows of $K$ bits in strict mode, i.e. the final running
+//! sum is constrained to be zero. Each window is constrained by
+//! `range_check(k_i, 2^K)`, a gate of degree $2^K + 1$.
+
+use ff::PrimeFieldBits;
+use halo2::{
+    arithmetic::FieldExt,
+    circuit::{AssignedCell, Layouter},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Fixed},
+};
+
+use crate::utilities::{decompose_running_sum::RunningSumConfig, range_check};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct RangeCheckConfig<F: FieldExt + PrimeFieldBits, const WINDOW_NUM_BITS: usize> {
+    running_sum: RunningSumConfig<F, WINDOW_NUM_BITS>,
+}
+
+impl<F: FieldExt + PrimeFieldBits, const WINDOW_NUM_BITS: usize>
+    RangeCheckConfig<F, WINDOW_NUM_BITS>
+{
+    /// # Side-effects
+    ///
+    /// `z` will be equality-enabled and `constants` will be used for
+    /// constants.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        z: Column<Advice>,
+        constants: Column<Fixed>,
+    ) -> Self {
+        // strict decomposition constrains the last running sum to the constant zero
+        meta.enable_constant(constants);
+
+        let q_range_check = meta.selector();
+        let running_sum = RunningSumConfig::configure(meta, q_range_check, z);
+
+        meta.create_gate("range check window", |meta| {
+            let q_range_check = meta.query_selector(running_sum.q_range_check());
+            let window = running_sum.window_expr(meta);
+
+            Constraints::with_selector(
+                q_range_check,
+                Some(("window", range_check(window, 1 << WINDOW_NUM_BITS))),
+            )
+        });
+
+        Self { running_sum }
+    }
+
+    /// Constrains `value` to be in range $[0, 2^{num\_bits})$.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_bits` is not a multiple of `WINDOW_NUM_BITS`.
+    pub fn range_check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        num_bits: usize,
+    ) -> Result<(), Error> {
+        assert_eq!(num_bits % WINDOW_NUM_BITS, 0);
+
+        layouter.assign_region(
+            || format!("range check {} bits", num_bits),
+            |mut region| {
+                self.running_sum.copy_decompose(
+                    &mut region,
+                    0,
+                    value.clone(),
+                    true,
+                    num_bits,
+                    num_bits / WINDOW_NUM_BITS,
+                )?;
+                Ok(())
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ff::PrimeFieldBits;
+    use halo2::{
+        arithmetic::{Field, FieldExt},
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        halo2curves::pasta::Fp as PastaFp,
+        plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
+    };
+
+    use super::RangeCheckConfig;
+
+    #[derive(Clone, Debug, Default)]
+    struct RangeCheckCircuit<F: FieldExt> {
+        value: Value<F>,
+        num_bits: usize,
+    }
+
+    impl<F: FieldExt + PrimeFieldBits> Circuit<F> for RangeCheckCircuit<F> {
+        type Config = (RangeCheckConfig<F, 2>, Column<Advice>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                value: Value::unknown(),
+                num_bits: self.num_bits,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let z = meta.advice_column();
+            let constants = meta.fixed_column();
+            (RangeCheckConfig::configure(meta, z, constants), z)
+        }
+
+        fn synthesize(
+            &self,
+            (config, z): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let value = layouter.assign_region(
+                || "value",
+                |mut region| region.assign_advice(|| "value", z, 0, || self.value),
+            )?;
+            config.range_check(layouter.namespace(|| "range check"), &value, self.num_bits)
+        }
+    }
+
+    #[test]
+    fn test_range_check() {
+        let run = |value: PastaFp, num_bits: usize| {
+            let circuit = RangeCheckCircuit {
+                value: Value::known(value),
+                num_bits,
+            };
+            let prover = MockProver::run(8, &circuit, vec![]).unwrap();
+            prover.verify()
+        };
+
+        assert_eq!(run(PastaFp::zero(), 40), Ok(()));
+        assert_eq!(run(PastaFp::from((1 << 40) - 1), 40), Ok(()));
+        assert_eq!(run(PastaFp::from(u64::MAX), 64), Ok(()));
+        assert!(run(PastaFp::from(1 << 40), 40).is_err());
+        assert!(run(-PastaFp::one(), 64).is_err());
+    }
+}
@@ -0,0 +1,406 @@
+use ff::PrimeFieldBits;
+use halo2::{
+    arithmetic::{CurveAffine, FieldExt},
+    circuit::Value,
+};
+
+use crate::{
+    convert_field,
+    ecdsa::{self, Ecdsa, Signature},
+    poseidon::{self, Domain, P128Pow5T3, Spec, P128_RATE, P128_WIDTH},
+    signature::ReceiptSignatureScheme,
+    ReceiptCircuit, ReceiptPublicInputs, SignatureMode,
+};
+
+/// Bit width of `now` and `expires_by` in circuits built by
+/// [`Receipt::to_circuit`].
+pub const DEFAULT_TIMESTAMP_NUM_BITS: usize = 64;
+/// Bit width of `amount` in circuits built by [`Receipt::to_circuit`].
+pub const DEFAULT_AMOUNT_NUM_BITS: usize = 64;
+/// Window size of the scalar multiplications in circuits built by
+/// [`Receipt::to_circuit`].
+pub const DEFAULT_WINDOW_SIZE: usize = 2;
+/// Depth of the account registry, allowing for 2^20 accounts.
+pub const DEFAULT_REGISTRY_DEPTH: usize = 20;
+
+/// Receipt issued by account `a_index` to account `b_index`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Receipt<F: FieldExt> {
+    pub a_index: F,
+    pub b_index: F,
+    pub amount: F,
+    pub expires_by: F,
+}
+
+impl<F: FieldExt> Receipt<F> {
+    pub fn fields(&self) -> [F; 4] {
+        [self.a_index, self.b_index, self.amount, self.expires_by]
+    }
+
+    /// Canonical encoding of the receipt, i.e. the little-endian
+    /// representation of `a_index`, `b_index`, `amount` and `expires_by`
+    /// concatenated in that order.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.fields()
+            .iter()
+            .flat_map(|field| field.to_repr().as_ref().to_vec())
+            .collect()
+    }
+
+    /// Inverse of [`Receipt::to_bytes`]. Returns `None` if `bytes` has the
+    /// wrong length or any field isn't canonically encoded.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let repr_len = F::Repr::default().as_ref().len();
+        if bytes.len() != 4 * repr_len {
+            return None;
+        }
+
+        let mut fields = bytes.chunks(repr_len).map(|chunk| {
+            let mut repr = F::Repr::default();
+            repr.as_mut().copy_from_slice(chunk);
+            Option::<F>::from(F::from_repr(repr))
+        });
+
+        Some(Self {
+            a_index: fields.next()??,
+            b_index: fields.next()??,
+            amount: fields.next()??,
+            expires_by: fields.next()??,
+        })
+    }
+}
+
+impl<F: FieldExt> Receipt<F>
+where
+    P128Pow5T3: Spec<F, P128_WIDTH, P128_RATE>,
+{
+    /// Poseidon commitment to the receipt, absorbing the [`Domain::Receipt`]
+    /// tag followed by `[a_index, b_index, amount, expires_by]` in that order.
+    ///
+    /// This is the value `ReceiptCircuit` computes in-circuit and verifies
+    /// the signature over.
+    pub fn hash(&self) -> F {
+        let [a_index, b_index, amount, expires_by] = self.fields();
+        poseidon::hash::<F, P128Pow5T3, P128_WIDTH, P128_RATE, 5>([
+            Domain::Receipt.tag(),
+            a_index,
+            b_index,
+            amount,
+            expires_by,
+        ])
+    }
+
+    /// The receipt hash lifted into the scalar field of `C`, i.e. the
+    /// message that is signed.
+    pub fn msg_hash<C: CurveAffine>(&self) -> C::Scalar {
+        convert_field::<F, C::Scalar>(self.hash())
+    }
+
+    /// Nullifier of the receipt for a redeemer holding `nullifier_secret`,
+    /// i.e. `Poseidon(Domain::Nullifier, nullifier_secret, hash)`.
+    ///
+    /// The [`Domain::Nullifier`] tag separates nullifiers from every other
+    /// Poseidon hash of three elements, such as the nodes of the registry.
+    pub fn nullifier(&self, nullifier_secret: F) -> F {
+        poseidon::hash::<F, P128Pow5T3, P128_WIDTH, P128_RATE, 3>([
+            Domain::Nullifier.tag(),
+            nullifier_secret,
+            self.hash(),
+        ])
+    }
+
+    /// Signs the receipt hash with secret key `sk`.
+    pub fn sign<C: CurveAffine>(&self, sk: C::Scalar) -> Signature<C> {
+        ecdsa::sign::<C>(sk, self.msg_hash::<C>())
+    }
+
+    /// Verifies `signature` by `pk` over the receipt hash.
+    pub fn verify<C: CurveAffine>(&self, pk: C, signature: Signature<C>) -> bool {
+        ecdsa::verify(pk, self.msg_hash::<C>(), signature)
+    }
+
+    /// Circuit proving that the ECDSA signature `b_signature` by
+    /// `b_public_key` is valid for this receipt and that it hasn't expired at
+    /// `now`, and exposing its nullifier for `nullifier_secret`.
+    ///
+    /// `b_registry_path` is the registry path of `b_public_key` at `b_index`,
+    /// e.g. from [`crate::merkle::IncrementalMerkleTree::path`], and sets the
+    /// registry depth of the circuit.
+    ///
+    /// Bit widths and window size are set to their defaults and can be
+    /// overridden on the returned circuit.
+    pub fn to_circuit<C: CurveAffine>(
+        &self,
+        b_public_key: C,
+        b_signature: Signature<C>,
+        now: F,
+        nullifier_secret: F,
+        b_registry_path: &[F],
+    ) -> ReceiptCircuit<Ecdsa<C>, F>
+    where
+        F: PrimeFieldBits,
+    {
+        self.to_circuit_with(
+            Ecdsa::new(DEFAULT_WINDOW_SIZE),
+            b_public_key,
+            b_signature,
+            now,
+            nullifier_secret,
+            b_registry_path,
+        )
+    }
+
+    /// Like [`Receipt::to_circuit`], for a signature of scheme `S` laid out
+    /// by `scheme`.
+    pub fn to_circuit_with<S: ReceiptSignatureScheme<F>>(
+        &self,
+        scheme: S,
+        b_public_key: S::PublicKey,
+        b_signature: S::Signature,
+        now: F,
+        nullifier_secret: F,
+        b_registry_path: &[F],
+    ) -> ReceiptCircuit<S, F>
+    where
+        F: PrimeFieldBits,
+    {
+        ReceiptCircuit {
+            a_index: Value::known(self.a_index),
+            b_index: Value::known(self.b_index),
+            amount: Value::known(self.amount),
+            expires_by: Value::known(self.expires_by),
+            b_public_key: Value::known(b_public_key),
+            b_signature: Value::known(b_signature),
+            b_registry_path: b_registry_path.iter().copied().map(Value::known).collect(),
+            a_public_key: Value::unknown(),
+            a_signature: Value::unknown(),
+            now: Value::known(now),
+            nullifier_secret: Value::known(nullifier_secret),
+            expose_expires_by: false,
+            signature_mode: SignatureMode::PayeeOnly,
+            timestamp_num_bits: DEFAULT_TIMESTAMP_NUM_BITS,
+            amount_num_bits: DEFAULT_AMOUNT_NUM_BITS,
+            scheme,
+        }
+    }
+
+    /// Public inputs of the circuit returned by [`Receipt::to_circuit`].
+    pub fn public_inputs<C: CurveAffine>(
+        &self,
+        b_public_key: C,
+        now: F,
+        nullifier_secret: F,
+        registry_root: F,
+    ) -> ReceiptPublicInputs<Ecdsa<C>, F> {
+        self.public_inputs_with::<Ecdsa<C>>(b_public_key, now, nullifier_secret, registry_root)
+    }
+
+    /// Public inputs of the circuit returned by [`Receipt::to_circuit_with`].
+    pub fn public_inputs_with<S: ReceiptSignatureScheme<F>>(
+        &self,
+        b_public_key: S::PublicKey,
+        now: F,
+        nullifier_secret: F,
+        registry_root: F,
+    ) -> ReceiptPublicInputs<S, F> {
+        ReceiptPublicInputs {
+            b_public_key,
+            a_index: self.a_index,
+            b_index: self.b_index,
+            amount: self.amount,
+            now,
+            nullifier: self.nullifier(nullifier_secret),
+            registry_root,
+            expires_by: None,
+            a_public_key: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2::{
+        arithmetic::{CurveAffine, Field},
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        halo2curves::{group::Curve, pasta::Fp as PastaFp, secp256k1::Secp256k1Affine},
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use maingate::{MainGate, MainGateConfig, MainGateInstructions, RegionCtx};
+    use rand::rngs::OsRng;
+
+    use super::Receipt;
+    use crate::poseidon::{Domain, P128Pow5T3, PoseidonChip, P128_RATE, P128_WIDTH};
+
+    /// Hashes `receipt` and its nullifier with `PoseidonChip` as
+    /// `ReceiptCircuit` does, and constrains them to `hash` and `nullifier`.
+    #[derive(Clone, Debug, Default)]
+    struct ReceiptHashCircuit {
+        receipt: Receipt<PastaFp>,
+        nullifier_secret: PastaFp,
+        hash: PastaFp,
+        nullifier: PastaFp,
+    }
+
+    impl Circuit<PastaFp> for ReceiptHashCircuit {
+        type Config = MainGateConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<PastaFp>) -> Self::Config {
+            MainGate::<PastaFp>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<PastaFp>,
+        ) -> Result<(), Error> {
+            let main_gate = MainGate::<PastaFp>::new(config.clone());
+            let poseidon_chip =
+                PoseidonChip::<PastaFp, P128Pow5T3, P128_WIDTH, P128_RATE>::new(config);
+
+            layouter.assign_region(
+                || "receipt hash",
+                |mut region| {
+                    let offset = &mut 0;
+                    let ctx = &mut RegionCtx::new(&mut region, offset);
+
+                    let [a_index, b_index, amount, expires_by] = self
+                        .receipt
+                        .fields()
+                        .map(|field| main_gate.assign_value(ctx, Value::known(field)));
+                    let receipt_tag = poseidon_chip.domain_tag(ctx, Domain::Receipt)?;
+                    let hash = poseidon_chip.hash(
+                        ctx,
+                        &[receipt_tag, a_index?, b_index?, amount?, expires_by?],
+                    )?;
+
+                    let nullifier_secret =
+                        main_gate.assign_value(ctx, Value::known(self.nullifier_secret))?;
+                    let nullifier_tag = poseidon_chip.domain_tag(ctx, Domain::Nullifier)?;
+                    let nullifier = poseidon_chip
+                        .hash(ctx, &[nullifier_tag, nullifier_secret, hash.clone()])?;
+
+                    let expected_hash = main_gate.assign_value(ctx, Value::known(self.hash))?;
+                    main_gate.assert_equal(ctx, &hash, &expected_hash)?;
+                    let expected_nullifier =
+                        main_gate.assign_value(ctx, Value::known(self.nullifier))?;
+                    main_gate.assert_equal(ctx, &nullifier, &expected_nullifier)
+                },
+            )
+        }
+    }
+
+    fn receipt() -> Receipt<PastaFp> {
+        Receipt {
+            a_index: PastaFp::from(1),
+            b_index: PastaFp::from(2),
+            amount: PastaFp::from(100),
+            expires_by: PastaFp::from(1_700_000_000),
+        }
+    }
+
+    #[test]
+    fn test_receipt_hash() {
+        let receipt = receipt();
+        let nullifier_secret = PastaFp::random(OsRng);
+
+        // off-circuit hashes match the chip
+        let circuit = ReceiptHashCircuit {
+            receipt,
+            nullifier_secret,
+            hash: receipt.hash(),
+            nullifier: receipt.nullifier(nullifier_secret),
+        };
+        let prover = MockProver::run(12, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let circuit = ReceiptHashCircuit {
+            hash: receipt.hash() + PastaFp::one(),
+            ..circuit
+        };
+        let prover = MockProver::run(12, &circuit, vec![vec![]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        // swapping fields changes the commitment, unlike a plain sum
+        let swapped = Receipt {
+            b_index: receipt.amount,
+            amount: receipt.b_index,
+            ..receipt
+        };
+        assert_ne!(receipt.hash(), swapped.hash());
+    }
+
+    #[test]
+    fn test_receipt_bytes() {
+        let receipt = receipt();
+        let bytes = receipt.to_bytes();
+        assert_eq!(bytes.len(), 4 * 32);
+        assert_eq!(Receipt::from_bytes(&bytes), Some(receipt));
+
+        assert_eq!(Receipt::<PastaFp>::from_bytes(&bytes[1..]), None);
+        // non-canonical `expires_by`
+        let mut bytes = bytes;
+        bytes[3 * 32..].copy_from_slice(&[0xff; 32]);
+        assert_eq!(Receipt::<PastaFp>::from_bytes(&bytes), None);
+    }
+
+    #[test]
+    fn test_receipt_signature() {
+        let receipt = receipt();
+        let g = Secp256k1Affine::generator();
+        let sk = <Secp256k1Affine as CurveAffine>::ScalarExt::random(OsRng);
+        let pk = (g * sk).to_affine();
+
+        let signature = receipt.sign::<Secp256k1Affine>(sk);
+        assert!(receipt.verify(pk, signature));
+
+        let tampered = Receipt {
+            amount: receipt.amount + PastaFp::one(),
+            ..receipt
+        };
+        assert!(!tampered.verify(pk, signature));
+
+        let other_pk = (g * <Secp256k1Affine as CurveAffine>::ScalarExt::random(OsRng)).to_affine();
+        assert!(!receipt.verify(other_pk, signature));
+    }
+
+    #[test]
+    fn test_receipt_nullifier() {
+        let receipt = receipt();
+        let secret = PastaFp::random(OsRng);
+
+        // the chip derives the same nullifier from the receipt and secret
+        let circuit = ReceiptHashCircuit {
+            receipt,
+            nullifier_secret: secret,
+            hash: receipt.hash(),
+            nullifier: receipt.nullifier(secret),
+        };
+        let prover = MockProver::run(12, &circuit, vec![vec![]]).unwrap();
+        assert_eq!(prover.verify(), Ok(()));
+
+        let circuit = ReceiptHashCircuit {
+            nullifier_secret: PastaFp::random(OsRng),
+            ..circuit
+        };
+        let prover = MockProver::run(12, &circuit, vec![vec![]]).unwrap();
+        assert!(prover.verify().is_err());
+
+        assert_ne!(receipt.nullifier(secret), receipt.hash());
+
+        let other = Receipt {
+            amount: receipt.amount + PastaFp::one(),
+            ..receipt
+        };
+        assert_ne!(receipt.nullifier(secret), other.nullifier(secret));
+        assert_ne!(
+            receipt.nullifier(secret),
+            receipt.nullifier(PastaFp::random(OsRng))
+        );
+    }
+}
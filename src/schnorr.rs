@@ -0,0 +1,758 @@
+//! Schnorr signatures over Pallas, verified natively over `pallas::Base`.
+//!
+//! A signature over message `m` by `pk = [sk] G` is a pair `(R, s)` with
+//! `R = [k] G` and `s = k + e * sk`, where the challenge is
+//! `e = Poseidon(Domain::SchnorrChallenge, R.x, R.y, pk.x, pk.y, m)`. It is
+//! valid iff `[s] G = R + [e] pk`.
+//!
+//! Coordinates of Pallas points are elements of the circuit's native field,
+//! so unlike ECDSA over secp256k1 no limb decomposition is needed. Both `e`
+//! and `s` are required to be below $2^{254}$, which makes their bit
+//! decompositions unique; the signer retries the one-in-$2^{128}$ nonces for
+//! which either isn't.
+//!
+//! Verification takes fewer rows than that of ECDSA over secp256k1 in
+//! `ReceiptCircuit`, whose non-native arithmetic works on 4 limbs of 68 bits,
+//! so receipts of our own clients are cheaper to prove with Schnorr. Rows of
+//! both verifications don't depend on the signature, and at the default
+//! registry depth the smallest `ReceiptCircuit` has $2^{16}$ rows with
+//! Schnorr against $2^{20}$ (`prover::RECEIPT_K`) with ECDSA over secp256k1
+//! and windows of 2 bits.
+
+use ff::PrimeField;
+use halo2::{
+    arithmetic::{CurveAffine, Field},
+    circuit::{Layouter, Value},
+    halo2curves::{
+        group::{Curve, Group},
+        pasta::{pallas, Fp, Fq},
+    },
+    plonk::{ConstraintSystem, Error},
+};
+use maingate::{
+    AssignedCondition, AssignedValue, MainGate, MainGateConfig, MainGateInstructions, RegionCtx,
+    Term,
+};
+use rand::rngs::OsRng;
+
+use crate::{
+    convert_field,
+    poseidon::{self, Domain, P128Pow5T3, PoseidonChip, P128_RATE, P128_WIDTH},
+    signature::ReceiptSignatureScheme,
+};
+
+/// Number of bits of the scalars `e` and `s`.
+pub(crate) const SCALAR_NUM_BITS: usize = 254;
+
+/// Schnorr signature `(R, s)`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SchnorrSignature {
+    pub r: pallas::Affine,
+    pub s: Fq,
+}
+
+fn fits_scalar_num_bits(repr: [u8; 32]) -> bool {
+    repr[31] >> (SCALAR_NUM_BITS - 248) == 0
+}
+
+fn challenge(r: pallas::Affine, pk: pallas::Affine, msg: Fp) -> Fp {
+    let r = r.coordinates().unwrap();
+    let pk = pk.coordinates().unwrap();
+    poseidon::hash::<Fp, P128Pow5T3, P128_WIDTH, P128_RATE, 6>([
+        Domain::SchnorrChallenge.tag(),
+        *r.x(),
+        *r.y(),
+        *pk.x(),
+        *pk.y(),
+        msg,
+    ])
+}
+
+/// Signs `msg` with secret key `sk`.
+pub fn sign(sk: Fq, msg: Fp) -> SchnorrSignature {
+    let g = pallas::Affine::generator();
+    let pk = (g * sk).to_affine();
+
+    loop {
+        let k = Fq::random(OsRng);
+        let r = (g * k).to_affine();
+        let e = challenge(r, pk, msg);
+        if !fits_scalar_num_bits(e.to_repr()) {
+            continue;
+        }
+
+        let s = k + convert_field::<Fp, Fq>(e) * sk;
+        if s.is_zero_vartime() || !fits_scalar_num_bits(s.to_repr()) {
+            continue;
+        }
+        return SchnorrSignature { r, s };
+    }
+}
+
+/// Verifies `signature` by `pk` over `msg`.
+pub fn verify(pk: pallas::Affine, msg: Fp, signature: SchnorrSignature) -> bool {
+    let SchnorrSignature { r, s } = signature;
+    if bool::from(pk.coordinates().is_none()) || bool::from(r.coordinates().is_none()) {
+        return false;
+    }
+
+    let e = challenge(r, pk, msg);
+    if !fits_scalar_num_bits(e.to_repr()) || !fits_scalar_num_bits(s.to_repr()) {
+        return false;
+    }
+
+    let g = pallas::Affine::generator();
+    g * s == pallas::Point::from(r) + pk * convert_field::<Fp, Fq>(e)
+}
+
+/// Pallas point with native coordinates. Never the identity.
+#[derive(Clone, Debug)]
+pub struct AssignedPallasPoint {
+    pub x: AssignedValue<Fp>,
+    pub y: AssignedValue<Fp>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AssignedSchnorrSignature {
+    pub r: AssignedPallasPoint,
+    pub s: AssignedValue<Fp>,
+}
+
+/// Verifies Schnorr signatures with `MainGate`.
+///
+/// Scalar multiplications are double-and-add with incomplete addition,
+/// starting from an auxiliary point `A` of unknown discrete logarithm so that
+/// the accumulator is never the identity. Additions constrain the
+/// x-coordinates to differ, so a prover that hits an exceptional case fails
+/// rather than proving a wrong result.
+#[derive(Clone, Debug)]
+pub struct SchnorrChip {
+    main_gate: MainGate<Fp>,
+    poseidon_chip: PoseidonChip<Fp, P128Pow5T3, P128_WIDTH, P128_RATE>,
+    aux: pallas::Affine,
+    /// `-[2^SCALAR_NUM_BITS] A`
+    aux_correction: pallas::Affine,
+}
+
+impl SchnorrChip {
+    pub fn new(main_gate_config: MainGateConfig) -> Self {
+        // try-and-increment from Poseidon outputs, so nobody knows the
+        // discrete logarithm of `A`
+        let aux = (0u64..)
+            .find_map(|i| {
+                let x = poseidon::hash::<Fp, P128Pow5T3, P128_WIDTH, P128_RATE, 2>([
+                    Domain::SchnorrAuxGenerator.tag(),
+                    Fp::from(i),
+                ]);
+                let y = Option::<Fp>::from((x.square() * x + Fp::from(5)).sqrt())?;
+                Option::from(pallas::Affine::from_xy(x, y))
+            })
+            .unwrap();
+        let aux_correction = -(0..SCALAR_NUM_BITS)
+            .fold(pallas::Point::from(aux), |acc, _| acc.double())
+            .to_affine();
+
+        Self {
+            main_gate: MainGate::new(main_gate_config.clone()),
+            poseidon_chip: PoseidonChip::new(main_gate_config),
+            aux,
+            aux_correction,
+        }
+    }
+
+    fn assert_on_curve(
+        &self,
+        ctx: &mut RegionCtx<'_, Fp>,
+        point: &AssignedPallasPoint,
+    ) -> Result<(), Error> {
+        let main_gate = &self.main_gate;
+
+        // y^2 = x^3 + 5
+        let y_square = main_gate.mul(ctx, &point.y, &point.y)?;
+        let x_square = main_gate.mul(ctx, &point.x, &point.x)?;
+        let x_cube = main_gate.mul(ctx, &x_square, &point.x)?;
+        let rhs = main_gate.add_constant(ctx, &x_cube, Fp::from(5))?;
+        main_gate.assert_equal(ctx, &y_square, &rhs)
+    }
+
+    /// Assigns `point`, constraining it to be on the curve.
+    pub fn assign_point(
+        &self,
+        ctx: &mut RegionCtx<'_, Fp>,
+        point: Value<pallas::Affine>,
+    ) -> Result<AssignedPallasPoint, Error> {
+        let coordinates = point.map(|point| {
+            let coordinates = point.coordinates().unwrap();
+            (*coordinates.x(), *coordinates.y())
+        });
+        let point = AssignedPallasPoint {
+            x: self
+                .main_gate
+                .assign_value(ctx, coordinates.map(|(x, _)| x))?,
+            y: self
+                .main_gate
+                .assign_value(ctx, coordinates.map(|(_, y)| y))?,
+        };
+        self.assert_on_curve(ctx, &point)?;
+        Ok(point)
+    }
+
+    fn assign_constant_point(
+        &self,
+        ctx: &mut RegionCtx<'_, Fp>,
+        point: pallas::Affine,
+    ) -> Result<AssignedPallasPoint, Error> {
+        let coordinates = point.coordinates().unwrap();
+        Ok(AssignedPallasPoint {
+            x: self.main_gate.assign_constant(ctx, *coordinates.x())?,
+            y: self.main_gate.assign_constant(ctx, *coordinates.y())?,
+        })
+    }
+
+    /// Assigns `signature`, constraining `R` to be on the curve.
+    pub fn assign_signature(
+        &self,
+        ctx: &mut RegionCtx<'_, Fp>,
+        signature: Value<SchnorrSignature>,
+    ) -> Result<AssignedSchnorrSignature, Error> {
+        let r = self.assign_point(ctx, signature.map(|signature| signature.r))?;
+        // `s` fits in the native field if it's a valid signature, and is
+        // constrained to `SCALAR_NUM_BITS` bits when verified
+        let s = signature.map(|signature| convert_field::<Fq, Fp>(signature.s));
+        let s = self.main_gate.assign_value(ctx, s)?;
+        Ok(AssignedSchnorrSignature { r, s })
+    }
+
+    /// `a + b`, where `a.x != b.x` is constrained.
+    fn add(
+        &self,
+        ctx: &mut RegionCtx<'_, Fp>,
+        a: &AssignedPallasPoint,
+        b: &AssignedPallasPoint,
+    ) -> Result<AssignedPallasPoint, Error> {
+        let main_gate = &self.main_gate;
+
+        let dx = main_gate.sub(ctx, &b.x, &a.x)?;
+        main_gate.assert_not_zero(ctx, &dx)?;
+        let dy = main_gate.sub(ctx, &b.y, &a.y)?;
+        let lambda = main_gate.div_unsafe(ctx, &dy, &dx)?;
+
+        self.complete_line(ctx, &lambda, a, &b.x)
+    }
+
+    /// `[2] a`
+    fn double(
+        &self,
+        ctx: &mut RegionCtx<'_, Fp>,
+        a: &AssignedPallasPoint,
+    ) -> Result<AssignedPallasPoint, Error> {
+        let main_gate = &self.main_gate;
+
+        // Pallas has prime order, so no point other than the identity has
+        // `y = 0`
+        let x_square = main_gate.mul(ctx, &a.x, &a.x)?;
+        let numerator =
+            main_gate.compose(ctx, &[Term::Assigned(&x_square, Fp::from(3))], Fp::zero())?;
+        let denominator = main_gate.add(ctx, &a.y, &a.y)?;
+        let lambda = main_gate.div_unsafe(ctx, &numerator, &denominator)?;
+
+        self.complete_line(ctx, &lambda, a, &a.x)
+    }
+
+    /// Third point on the line of slope `lambda` through `a` and a point with
+    /// x-coordinate `other_x`, negated.
+    fn complete_line(
+        &self,
+        ctx: &mut RegionCtx<'_, Fp>,
+        lambda: &AssignedValue<Fp>,
+        a: &AssignedPallasPoint,
+        other_x: &AssignedValue<Fp>,
+    ) -> Result<AssignedPallasPoint, Error> {
+        let main_gate = &self.main_gate;
+
+        // x = lambda^2 - a.x - other_x
+        let lambda_square = main_gate.mul(ctx, lambda, lambda)?;
+        let x = main_gate.compose(
+            ctx,
+            &[
+                Term::Assigned(&lambda_square, Fp::one()),
+                Term::Assigned(&a.x, -Fp::one()),
+                Term::Assigned(other_x, -Fp::one()),
+            ],
+            Fp::zero(),
+        )?;
+        // y = lambda * (a.x - x) - a.y
+        let dx = main_gate.sub(ctx, &a.x, &x)?;
+        let y = main_gate.mul(ctx, lambda, &dx)?;
+        let y = main_gate.sub(ctx, &y, &a.y)?;
+
+        Ok(AssignedPallasPoint { x, y })
+    }
+
+    fn select(
+        &self,
+        ctx: &mut RegionCtx<'_, Fp>,
+        a: &AssignedPallasPoint,
+        b: &AssignedPallasPoint,
+        cond: &AssignedCondition<Fp>,
+    ) -> Result<AssignedPallasPoint, Error> {
+        Ok(AssignedPallasPoint {
+            x: self.main_gate.select(ctx, &a.x, &b.x, cond)?,
+            y: self.main_gate.select(ctx, &a.y, &b.y, cond)?,
+        })
+    }
+
+    /// `[scalar] point`, where `scalar` is constrained to `SCALAR_NUM_BITS`
+    /// bits.
+    fn mul(
+        &self,
+        ctx: &mut RegionCtx<'_, Fp>,
+        point: &AssignedPallasPoint,
+        scalar: &AssignedValue<Fp>,
+    ) -> Result<AssignedPallasPoint, Error> {
+        let bits = self.main_gate.to_bits(ctx, scalar, SCALAR_NUM_BITS)?;
+
+        // acc = [2^SCALAR_NUM_BITS] A + [scalar] point
+        let mut acc = self.assign_constant_point(ctx, self.aux)?;
+        for bit in bits.iter().rev() {
+            acc = self.double(ctx, &acc)?;
+            let sum = self.add(ctx, &acc, point)?;
+            acc = self.select(ctx, &sum, &acc, bit)?;
+        }
+
+        let correction = self.assign_constant_point(ctx, self.aux_correction)?;
+        self.add(ctx, &acc, &correction)
+    }
+
+    /// `sum [scalar_i] base_i` for fixed bases `base_i` and scalars given by
+    /// their little-endian bits.
+    ///
+    /// Each bit selects between adding the constant `[2^j] base_i` or not, so
+    /// no doubling is needed. The result must not be `-[2] A` or the
+    /// identity, which is infeasible for bases of unknown relative discrete
+    /// logarithms unless every scalar is zero.
+    pub fn fixed_base_msm(
+        &self,
+        ctx: &mut RegionCtx<'_, Fp>,
+        terms: &[(pallas::Affine, &[AssignedCondition<Fp>])],
+    ) -> Result<AssignedPallasPoint, Error> {
+        // acc = A + sum [scalar_i] base_i
+        let mut acc = self.assign_constant_point(ctx, self.aux)?;
+        for (base, bits) in terms {
+            let mut power = pallas::Point::from(*base);
+            for bit in bits.iter() {
+                let addend = self.assign_constant_point(ctx, power.to_affine())?;
+                let sum = self.add(ctx, &acc, &addend)?;
+                acc = self.select(ctx, &sum, &acc, bit)?;
+                power = power.double();
+            }
+        }
+
+        let correction = self.assign_constant_point(ctx, -self.aux)?;
+        self.add(ctx, &acc, &correction)
+    }
+
+    /// Constrains `signature` by `pk` over `msg` to be valid.
+    pub fn verify(
+        &self,
+        ctx: &mut RegionCtx<'_, Fp>,
+        pk: &AssignedPallasPoint,
+        msg: &AssignedValue<Fp>,
+        signature: &AssignedSchnorrSignature,
+    ) -> Result<(), Error> {
+        let AssignedSchnorrSignature { r, s } = signature;
+
+        let tag = self
+            .poseidon_chip
+            .domain_tag(ctx, Domain::SchnorrChallenge)?;
+        let e = self.poseidon_chip.hash(
+            ctx,
+            &[
+                tag,
+                r.x.clone(),
+                r.y.clone(),
+                pk.x.clone(),
+                pk.y.clone(),
+                msg.clone(),
+            ],
+        )?;
+
+        let g = self.assign_constant_point(ctx, pallas::Affine::generator())?;
+        let lhs = self.mul(ctx, &g, s)?;
+        let e_pk = self.mul(ctx, pk, &e)?;
+        let rhs = self.add(ctx, r, &e_pk)?;
+
+        self.main_gate.assert_equal(ctx, &lhs.x, &rhs.x)?;
+        self.main_gate.assert_equal(ctx, &lhs.y, &rhs.y)
+    }
+}
+
+/// Schnorr over Pallas as a receipt signature scheme, for circuits over
+/// `pallas::Base`. The signed message is the receipt hash itself.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Schnorr;
+
+impl ReceiptSignatureScheme<Fp> for Schnorr {
+    type PublicKey = pallas::Affine;
+    type Signature = SchnorrSignature;
+
+    type Config = MainGateConfig;
+    type Chip = SchnorrChip;
+    type AssignedPublicKey = AssignedPallasPoint;
+    type AssignedSignature = AssignedSchnorrSignature;
+
+    fn configure(
+        _meta: &mut ConstraintSystem<Fp>,
+        main_gate_config: &MainGateConfig,
+    ) -> Self::Config {
+        main_gate_config.clone()
+    }
+
+    fn load_chip(
+        &self,
+        config: &Self::Config,
+        _layouter: &mut impl Layouter<Fp>,
+    ) -> Result<Self::Chip, Error> {
+        Ok(SchnorrChip::new(config.clone()))
+    }
+
+    fn load_tables(_config: &Self::Config, _layouter: &mut impl Layouter<Fp>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn assign_public_key(
+        chip: &Self::Chip,
+        ctx: &mut RegionCtx<'_, Fp>,
+        public_key: Value<pallas::Affine>,
+    ) -> Result<Self::AssignedPublicKey, Error> {
+        chip.assign_point(ctx, public_key)
+    }
+
+    fn assign_signature(
+        chip: &Self::Chip,
+        ctx: &mut RegionCtx<'_, Fp>,
+        signature: Value<SchnorrSignature>,
+    ) -> Result<Self::AssignedSignature, Error> {
+        chip.assign_signature(ctx, signature)
+    }
+
+    fn verify(
+        chip: &Self::Chip,
+        ctx: &mut RegionCtx<'_, Fp>,
+        public_key: &Self::AssignedPublicKey,
+        msg: &AssignedValue<Fp>,
+        signature: &Self::AssignedSignature,
+    ) -> Result<(), Error> {
+        chip.verify(ctx, public_key, msg, signature)
+    }
+
+    fn public_key_cells(public_key: &Self::AssignedPublicKey) -> Vec<AssignedValue<Fp>> {
+        vec![public_key.x.clone(), public_key.y.clone()]
+    }
+
+    fn public_key_values(public_key: &pallas::Affine) -> Vec<Fp> {
+        let coordinates = public_key.coordinates().unwrap();
+        vec![*coordinates.x(), *coordinates.y()]
+    }
+
+    fn shape_parameters(&self) -> Vec<u64> {
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2::{
+        arithmetic::{CurveAffine, Field},
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        halo2curves::{
+            group::Curve,
+            pasta::{pallas, Fp, Fq},
+            secp256k1::Secp256k1Affine,
+        },
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use maingate::{MainGate, MainGateConfig, MainGateInstructions, RegionCtx};
+    use rand_core::OsRng;
+
+    use super::{sign, verify, Schnorr, SchnorrChip, SchnorrSignature};
+    use crate::{
+        ecdsa::{self, Ecdsa},
+        merkle::{scheme_registry_leaf, secret_commitment, IncrementalMerkleTree},
+        prover::RECEIPT_K,
+        signature::ReceiptSignatureScheme,
+        utilities::RowCount,
+        Receipt, ReceiptCircuit, ReceiptConfig, DEFAULT_REGISTRY_DEPTH, DEFAULT_WINDOW_SIZE,
+    };
+
+    #[test]
+    fn test_schnorr_signature() {
+        let g = pallas::Affine::generator();
+        let sk = Fq::random(OsRng);
+        let pk = (g * sk).to_affine();
+        let msg = Fp::random(OsRng);
+
+        let signature = sign(sk, msg);
+        assert!(verify(pk, msg, signature));
+
+        assert!(!verify(pk, msg + Fp::one(), signature));
+        assert!(!verify((g * Fq::random(OsRng)).to_affine(), msg, signature));
+        assert!(!verify(
+            pk,
+            msg,
+            SchnorrSignature {
+                s: signature.s + Fq::one(),
+                ..signature
+            }
+        ));
+    }
+
+    #[derive(Debug, Default)]
+    struct SchnorrCircuit {
+        pk: Value<pallas::Affine>,
+        msg: Value<Fp>,
+        signature: Value<SchnorrSignature>,
+        /// Rows taken by the verification.
+        rows: RowCount,
+    }
+
+    impl Circuit<Fp> for SchnorrCircuit {
+        type Config = MainGateConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            MainGate::<Fp>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let main_gate = MainGate::<Fp>::new(config.clone());
+            let schnorr_chip = SchnorrChip::new(config);
+
+            layouter.assign_region(
+                || "verify schnorr",
+                |mut region| {
+                    let offset = &mut 0;
+                    let ctx = &mut RegionCtx::new(&mut region, offset);
+
+                    let pk = schnorr_chip.assign_point(ctx, self.pk)?;
+                    let msg = main_gate.assign_value(ctx, self.msg)?;
+                    let signature = schnorr_chip.assign_signature(ctx, self.signature)?;
+                    self.rows
+                        .record(ctx, |ctx| schnorr_chip.verify(ctx, &pk, &msg, &signature))
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn test_schnorr_chip() {
+        let g = pallas::Affine::generator();
+        let sk = Fq::random(OsRng);
+        let pk = (g * sk).to_affine();
+        let msg = Fp::random(OsRng);
+        let signature = sign(sk, msg);
+
+        let run = |pk: pallas::Affine, msg: Fp, signature: SchnorrSignature| {
+            let circuit = SchnorrCircuit {
+                pk: Value::known(pk),
+                msg: Value::known(msg),
+                signature: Value::known(signature),
+                ..Default::default()
+            };
+            MockProver::run(14, &circuit, vec![vec![]])
+                .unwrap()
+                .verify()
+        };
+
+        assert_eq!(run(pk, msg, signature), Ok(()));
+        assert!(run(pk, msg + Fp::one(), signature).is_err());
+        assert!(run((g * Fq::random(OsRng)).to_affine(), msg, signature).is_err());
+        assert!(run(
+            pk,
+            msg,
+            SchnorrSignature {
+                s: signature.s + Fq::one(),
+                ..signature
+            }
+        )
+        .is_err());
+        assert!(run(
+            pk,
+            msg,
+            SchnorrSignature {
+                r: (g * Fq::random(OsRng)).to_affine(),
+                ..signature
+            }
+        )
+        .is_err());
+    }
+
+    /// ECDSA verification over secp256k1 as done by `ReceiptCircuit`.
+    #[derive(Debug, Default)]
+    struct EcdsaCircuit {
+        pk: Value<Secp256k1Affine>,
+        msg: Value<Fp>,
+        signature: Value<ecdsa::Signature<Secp256k1Affine>>,
+        scheme: Ecdsa<Secp256k1Affine>,
+        /// Rows taken by the verification.
+        rows: RowCount,
+    }
+
+    impl Circuit<Fp> for EcdsaCircuit {
+        type Config = ReceiptConfig<Fp, Ecdsa<Secp256k1Affine>>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                scheme: self.scheme,
+                ..Default::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            ReceiptConfig::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let main_gate = MainGate::<Fp>::new(config.main_gate_config.clone());
+            let ecdsa_chip = self
+                .scheme
+                .load_chip(&config.signature_config, &mut layouter)?;
+
+            layouter.assign_region(
+                || "verify ecdsa",
+                |mut region| {
+                    let offset = &mut 0;
+                    let ctx = &mut RegionCtx::new(&mut region, offset);
+
+                    let pk =
+                        Ecdsa::<Secp256k1Affine>::assign_public_key(&ecdsa_chip, ctx, self.pk)?;
+                    let msg = main_gate.assign_value(ctx, self.msg)?;
+                    let signature = Ecdsa::<Secp256k1Affine>::assign_signature(
+                        &ecdsa_chip,
+                        ctx,
+                        self.signature,
+                    )?;
+                    self.rows.record(ctx, |ctx| {
+                        Ecdsa::<Secp256k1Affine>::verify(&ecdsa_chip, ctx, &pk, &msg, &signature)
+                    })
+                },
+            )?;
+
+            Ecdsa::<Secp256k1Affine>::load_tables(&config.signature_config, &mut layouter)
+        }
+    }
+
+    /// `ReceiptCircuit` of scheme `S` at the default registry depth, with the
+    /// receipt signed by `sign`, and its instances.
+    fn receipt_circuit<S: ReceiptSignatureScheme<Fp>>(
+        scheme: S,
+        pk: S::PublicKey,
+        sign: impl FnOnce(&Receipt<Fp>) -> S::Signature,
+    ) -> (ReceiptCircuit<S, Fp>, Vec<Vec<Fp>>) {
+        let now = Fp::from(1_600_000_000);
+        let nullifier_secret = Fp::random(OsRng);
+        let receipt = Receipt {
+            a_index: Fp::from(1),
+            b_index: Fp::from(0),
+            amount: Fp::from(100),
+            expires_by: Fp::from(1_700_000_000),
+        };
+
+        let mut registry = IncrementalMerkleTree::new(DEFAULT_REGISTRY_DEPTH);
+        registry.insert(scheme_registry_leaf::<S, _>(
+            receipt.b_index,
+            &pk,
+            secret_commitment(nullifier_secret),
+        ));
+
+        let circuit = receipt.to_circuit_with(
+            scheme,
+            pk,
+            sign(&receipt),
+            now,
+            nullifier_secret,
+            &registry.path(0),
+        );
+        let instances = receipt
+            .public_inputs_with::<S>(pk, now, nullifier_secret, registry.root())
+            .instances();
+        (circuit, instances)
+    }
+
+    /// Whether `circuit` is satisfied in `2^k` rows.
+    fn fits(k: u32, circuit: &impl Circuit<Fp>, instances: Vec<Vec<Fp>>) -> bool {
+        MockProver::run(k, circuit, instances).map_or(false, |prover| prover.verify().is_ok())
+    }
+
+    #[test]
+    fn test_row_count() {
+        let schnorr = [(); 2].map(|_| {
+            let sk = Fq::random(OsRng);
+            let msg = Fp::random(OsRng);
+            let circuit = SchnorrCircuit {
+                pk: Value::known((pallas::Affine::generator() * sk).to_affine()),
+                msg: Value::known(msg),
+                signature: Value::known(sign(sk, msg)),
+                ..Default::default()
+            };
+            let prover = MockProver::run(14, &circuit, vec![vec![]]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+            circuit.rows.get()
+        });
+
+        let ecdsa = [(); 2].map(|_| {
+            let sk = <Secp256k1Affine as CurveAffine>::ScalarExt::random(OsRng);
+            let msg = Fp::random(OsRng);
+            let circuit = EcdsaCircuit {
+                pk: Value::known((Secp256k1Affine::generator() * sk).to_affine()),
+                msg: Value::known(msg),
+                signature: Value::known(ecdsa::sign::<Secp256k1Affine>(
+                    sk,
+                    crate::convert_field(msg),
+                )),
+                scheme: Ecdsa::new(DEFAULT_WINDOW_SIZE),
+                ..Default::default()
+            };
+            let prover = MockProver::run(20, &circuit, vec![vec![]]).unwrap();
+            assert_eq!(prover.verify(), Ok(()));
+            circuit.rows.get()
+        });
+
+        // see the module docs
+        assert_eq!(schnorr[0], schnorr[1]);
+        assert_eq!(ecdsa[0], ecdsa[1]);
+        assert!(schnorr[0] < ecdsa[0]);
+
+        let sk = Fq::random(OsRng);
+        let (circuit, instances) = receipt_circuit(
+            Schnorr,
+            (pallas::Affine::generator() * sk).to_affine(),
+            |receipt| sign(sk, receipt.hash()),
+        );
+        assert!(fits(16, &circuit, instances.clone()));
+        assert!(!fits(15, &circuit, instances));
+
+        let sk = <Secp256k1Affine as CurveAffine>::ScalarExt::random(OsRng);
+        let (circuit, instances) = receipt_circuit(
+            Ecdsa::new(DEFAULT_WINDOW_SIZE),
+            (Secp256k1Affine::generator() * sk).to_affine(),
+            |receipt| receipt.sign::<Secp256k1Affine>(sk),
+        );
+        assert!(fits(RECEIPT_K, &circuit, instances.clone()));
+        assert!(!fits(RECEIPT_K - 1, &circuit, instances));
+    }
+}
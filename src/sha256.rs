@@ -0,0 +1,541 @@
+//! SHA-256 over `MainGate`.
+//!
+//! Words are assigned as 32 little-endian bits, so rotations and shifts are
+//! free and every boolean function costs one or two rows per bit. Additions
+//! modulo 2^32 compose the bits of the summands and keep the low 32 bits of
+//! the sum. Messages have a variable length up to the capacity of the chip
+//! input and are padded in-circuit, so one circuit hashes every message that
+//! fits.
+
+use halo2::{arithmetic::FieldExt, circuit::Value, plonk::Error};
+use maingate::{
+    AssignedCondition, AssignedValue, MainGate, MainGateConfig, MainGateInstructions, RegionCtx,
+    Term,
+};
+use sha2::{Digest, Sha256};
+
+const INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const ROUND_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Bytes per block.
+const BLOCK_LEN: usize = 64;
+/// Bit width of the message length appended by the padding.
+const LENGTH_NUM_BITS: usize = 64;
+
+/// SHA-256 digest of `message`.
+pub fn sha256(message: &[u8]) -> [u8; 32] {
+    Sha256::digest(message).into()
+}
+
+/// Number of blocks hashed by [`Sha256Chip::digest`] for messages of up to
+/// `max_len` bytes, i.e. the number of blocks of the longest padded message.
+pub fn num_blocks(max_len: usize) -> usize {
+    (max_len + LENGTH_NUM_BITS / 8) / BLOCK_LEN + 1
+}
+
+/// Little-endian bits of a 32-bit word. Shifted words have fewer bits, the
+/// missing high bits being zero.
+type Word<F> = Vec<AssignedCondition<F>>;
+
+fn rotate_right<F: FieldExt>(word: &Word<F>, n: usize) -> Word<F> {
+    (0..32).map(|i| word[(i + n) % 32].clone()).collect()
+}
+
+fn shift_right<F: FieldExt>(word: &Word<F>, n: usize) -> Word<F> {
+    word[n..].to_vec()
+}
+
+/// Assigned SHA-256 digest, as 8 words from the most significant one.
+#[derive(Clone, Debug)]
+pub struct AssignedDigest<F: FieldExt> {
+    words: Vec<Word<F>>,
+}
+
+impl<F: FieldExt> AssignedDigest<F> {
+    /// Bits of the digest read as a big-endian integer, from the least
+    /// significant one.
+    pub fn to_le_bits(&self) -> Vec<AssignedCondition<F>> {
+        self.words.iter().rev().flatten().cloned().collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Sha256Chip<F: FieldExt> {
+    main_gate: MainGate<F>,
+}
+
+impl<F: FieldExt> Sha256Chip<F> {
+    pub fn new(main_gate_config: MainGateConfig) -> Self {
+        Self {
+            main_gate: MainGate::new(main_gate_config),
+        }
+    }
+
+    /// Assigns the bytes of `message` padded with zeros to `max_len` bytes,
+    /// and its length.
+    pub fn assign_message(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        message: Value<&[u8]>,
+        max_len: usize,
+    ) -> Result<(Vec<AssignedValue<F>>, AssignedValue<F>), Error> {
+        let bytes = (0..max_len)
+            .map(|i| {
+                let byte = message.map(|message| F::from(*message.get(i).unwrap_or(&0) as u64));
+                self.main_gate.assign_value(ctx, byte)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let len = message.map(|message| F::from(message.len() as u64));
+        let len = self.main_gate.assign_value(ctx, len)?;
+
+        Ok((bytes, len))
+    }
+
+    /// Digest of the first `len` bytes of `message`.
+    ///
+    /// The other bytes of `message` are constrained to be zero and `len` to
+    /// be at most `message.len()`, which sets the number of hashed blocks,
+    /// see [`num_blocks`].
+    pub fn digest(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        message: &[AssignedValue<F>],
+        len: &AssignedValue<F>,
+    ) -> Result<AssignedDigest<F>, Error> {
+        let main_gate = &self.main_gate;
+        let max_len = message.len();
+        let num_blocks = num_blocks(max_len);
+
+        let zero = main_gate.assign_constant(ctx, F::zero())?;
+        let one = main_gate.assign_constant(ctx, F::one())?;
+
+        // `is_end[i]` is set iff `i = len`, and `is_past[i]` iff `i >= len`
+        let mut is_end = vec![];
+        let mut is_past = vec![];
+        let mut past = zero.clone();
+        for i in 0..=max_len {
+            let diff = main_gate.add_constant(ctx, len, -F::from(i as u64))?;
+            let end = main_gate.is_zero(ctx, &diff)?;
+            past = main_gate.add(ctx, &past, &end)?;
+            is_end.push(end);
+            is_past.push(past.clone());
+        }
+        // `len <= max_len`
+        main_gate.assert_equal(ctx, &past, &one)?;
+
+        // The padding ends in the block `(len + 8) / 64`, with the length of
+        // the message in bits as 8 big-endian bytes.
+        let last_block = len.value().map(|len| {
+            F::from(((len.get_lower_128() as usize + LENGTH_NUM_BITS / 8) / BLOCK_LEN) as u64)
+        });
+        let last_block = main_gate.assign_value(ctx, last_block)?;
+        let mut is_last = vec![];
+        let mut count = zero.clone();
+        for block in 0..num_blocks {
+            let diff = main_gate.add_constant(ctx, &last_block, -F::from(block as u64))?;
+            let last = main_gate.is_zero(ctx, &diff)?;
+            count = main_gate.add(ctx, &count, &last)?;
+            is_last.push(last);
+        }
+        main_gate.assert_equal(ctx, &count, &one)?;
+        let remainder = main_gate.compose(
+            ctx,
+            &[
+                Term::Assigned(len, F::one()),
+                Term::Assigned(&last_block, -F::from(BLOCK_LEN as u64)),
+            ],
+            F::from((LENGTH_NUM_BITS / 8) as u64),
+        )?;
+        main_gate.to_bits(ctx, &remainder, 6)?;
+
+        let len_bits = main_gate.to_bits(ctx, len, LENGTH_NUM_BITS - 3)?;
+        let bit_len_bits: Vec<_> = std::iter::repeat(zero.clone())
+            .take(3)
+            .chain(len_bits)
+            .collect();
+        let bit_len_bytes = bit_len_bits
+            .chunks(8)
+            .rev()
+            .map(|bits| self.compose_bits(ctx, bits))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        // bits of every padded byte
+        let mut padded = vec![];
+        for i in 0..num_blocks * BLOCK_LEN {
+            let (block, offset) = (i / BLOCK_LEN, i % BLOCK_LEN);
+
+            let length_byte = if offset >= BLOCK_LEN - LENGTH_NUM_BITS / 8 {
+                let byte = &bit_len_bytes[offset - (BLOCK_LEN - LENGTH_NUM_BITS / 8)];
+                Some(main_gate.mul(ctx, &is_last[block], byte)?)
+            } else {
+                None
+            };
+
+            let mut terms = vec![];
+            if let Some(byte) = message.get(i) {
+                let past_byte = main_gate.mul(ctx, byte, &is_past[i])?;
+                main_gate.assert_equal(ctx, &past_byte, &zero)?;
+                terms.push(Term::Assigned(byte, F::one()));
+            }
+            if let Some(end) = is_end.get(i) {
+                terms.push(Term::Assigned(end, F::from(0x80)));
+            }
+            if let Some(length_byte) = length_byte.as_ref() {
+                terms.push(Term::Assigned(length_byte, F::one()));
+            }
+
+            let bits = if terms.is_empty() {
+                vec![zero.clone(); 8]
+            } else {
+                let byte = main_gate.compose(ctx, &terms, F::zero())?;
+                main_gate.to_bits(ctx, &byte, 8)?
+            };
+            padded.push(bits);
+        }
+
+        let constant_bit = |bit: u32| if bit == 1 { one.clone() } else { zero.clone() };
+        let mut state: Vec<Word<F>> = INITIAL_STATE
+            .iter()
+            .map(|word| (0..32).map(|i| constant_bit((word >> i) & 1)).collect())
+            .collect();
+
+        // the state after the last block, as composed words
+        let mut digest: Vec<AssignedValue<F>> = vec![];
+        for (block, is_last) in padded.chunks(BLOCK_LEN).zip(is_last.iter()) {
+            let words: Vec<Word<F>> = block
+                .chunks(4)
+                .map(|bytes| bytes.iter().rev().flatten().cloned().collect())
+                .collect();
+            state = self.compress(ctx, &state, &words)?;
+
+            let words = state
+                .iter()
+                .map(|word| self.compose_bits(ctx, word))
+                .collect::<Result<Vec<_>, Error>>()?;
+            digest = if digest.is_empty() {
+                words
+            } else {
+                words
+                    .iter()
+                    .zip(digest.iter())
+                    .map(|(word, digest)| main_gate.select(ctx, word, digest, is_last))
+                    .collect::<Result<Vec<_>, Error>>()?
+            };
+        }
+
+        let words = digest
+            .iter()
+            .map(|word| main_gate.to_bits(ctx, word, 32))
+            .collect::<Result<Vec<_>, Error>>()?;
+        Ok(AssignedDigest { words })
+    }
+
+    /// Big-endian bytes of `digest`.
+    pub fn digest_bytes(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        digest: &AssignedDigest<F>,
+    ) -> Result<Vec<AssignedValue<F>>, Error> {
+        digest
+            .words
+            .iter()
+            .flat_map(|word| word.chunks(8).rev())
+            .map(|bits| self.compose_bits(ctx, bits))
+            .collect()
+    }
+
+    fn compose_bits(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        bits: &[AssignedCondition<F>],
+    ) -> Result<AssignedValue<F>, Error> {
+        let terms: Vec<Term<F>> = bits
+            .iter()
+            .enumerate()
+            .map(|(i, bit)| Term::Assigned(bit, F::from(1 << i)))
+            .collect();
+        self.main_gate.compose(ctx, &terms, F::zero())
+    }
+
+    fn xor(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &AssignedCondition<F>,
+        b: &AssignedCondition<F>,
+    ) -> Result<AssignedCondition<F>, Error> {
+        // a + b - 2ab
+        let ab = self.main_gate.mul(ctx, a, b)?;
+        self.main_gate.compose(
+            ctx,
+            &[
+                Term::Assigned(a, F::one()),
+                Term::Assigned(b, F::one()),
+                Term::Assigned(&ab, -F::from(2)),
+            ],
+            F::zero(),
+        )
+    }
+
+    /// `a ^ b ^ c`, where `c` may be a shifted word.
+    fn xor3(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &Word<F>,
+        b: &Word<F>,
+        c: &Word<F>,
+    ) -> Result<Word<F>, Error> {
+        (0..32)
+            .map(|i| {
+                let ab = self.xor(ctx, &a[i], &b[i])?;
+                match c.get(i) {
+                    Some(c) => self.xor(ctx, &ab, c),
+                    None => Ok(ab),
+                }
+            })
+            .collect()
+    }
+
+    /// `(e & f) ^ (!e & g)`, i.e. `f` where `e` is set and `g` elsewhere.
+    fn ch(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        e: &Word<F>,
+        f: &Word<F>,
+        g: &Word<F>,
+    ) -> Result<Word<F>, Error> {
+        (0..32)
+            .map(|i| self.main_gate.select(ctx, &f[i], &g[i], &e[i]))
+            .collect()
+    }
+
+    /// `(a & b) ^ (a & c) ^ (b & c)`, i.e. `c` where `a` and `b` differ and
+    /// `a` elsewhere.
+    fn maj(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        a: &Word<F>,
+        b: &Word<F>,
+        c: &Word<F>,
+    ) -> Result<Word<F>, Error> {
+        (0..32)
+            .map(|i| {
+                let differ = self.xor(ctx, &a[i], &b[i])?;
+                self.main_gate.select(ctx, &c[i], &a[i], &differ)
+            })
+            .collect()
+    }
+
+    /// Sum of `words` and `constant` modulo 2^32.
+    fn add(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        words: &[&Word<F>],
+        constant: u32,
+    ) -> Result<Word<F>, Error> {
+        let terms: Vec<Term<F>> = words
+            .iter()
+            .flat_map(|word| {
+                word.iter()
+                    .enumerate()
+                    .map(|(i, bit)| Term::Assigned(bit, F::from(1 << i)))
+            })
+            .collect();
+        let sum = self
+            .main_gate
+            .compose(ctx, &terms, F::from(constant as u64))?;
+
+        // the sum of `n` words and a constant is less than `(n + 1) * 2^32`
+        let carry_num_bits = (usize::BITS - words.len().leading_zeros()) as usize;
+        let mut bits = self.main_gate.to_bits(ctx, &sum, 32 + carry_num_bits)?;
+        bits.truncate(32);
+        Ok(bits)
+    }
+
+    /// Compression function applied to `state` and the 16 words of a block.
+    fn compress(
+        &self,
+        ctx: &mut RegionCtx<'_, F>,
+        state: &[Word<F>],
+        block: &[Word<F>],
+    ) -> Result<Vec<Word<F>>, Error> {
+        // message schedule
+        let mut w = block.to_vec();
+        for t in 16..64 {
+            let s0 = self.xor3(
+                ctx,
+                &rotate_right(&w[t - 15], 7),
+                &rotate_right(&w[t - 15], 18),
+                &shift_right(&w[t - 15], 3),
+            )?;
+            let s1 = self.xor3(
+                ctx,
+                &rotate_right(&w[t - 2], 17),
+                &rotate_right(&w[t - 2], 19),
+                &shift_right(&w[t - 2], 10),
+            )?;
+            let word = self.add(ctx, &[&w[t - 16], &s0, &w[t - 7], &s1], 0)?;
+            w.push(word);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h]: [Word<F>; 8] =
+            state.to_vec().try_into().unwrap();
+        for (w, k) in w.iter().zip(ROUND_CONSTANTS) {
+            let s1 = self.xor3(
+                ctx,
+                &rotate_right(&e, 6),
+                &rotate_right(&e, 11),
+                &rotate_right(&e, 25),
+            )?;
+            let ch = self.ch(ctx, &e, &f, &g)?;
+            let s0 = self.xor3(
+                ctx,
+                &rotate_right(&a, 2),
+                &rotate_right(&a, 13),
+                &rotate_right(&a, 22),
+            )?;
+            let maj = self.maj(ctx, &a, &b, &c)?;
+
+            // e' = d + t1 and a' = t1 + t2, with t1 = h + s1 + ch + k + w and
+            // t2 = s0 + maj
+            let next_e = self.add(ctx, &[&d, &h, &s1, &ch, w], k)?;
+            let next_a = self.add(ctx, &[&h, &s1, &ch, w, &s0, &maj], k)?;
+
+            h = g;
+            g = f;
+            f = e;
+            e = next_e;
+            d = c;
+            c = b;
+            b = a;
+            a = next_a;
+        }
+
+        [a, b, c, d, e, f, g, h]
+            .iter()
+            .zip(state.iter())
+            .map(|(word, previous)| self.add(ctx, &[word, previous], 0))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use halo2::{
+        arithmetic::FieldExt,
+        circuit::{Layouter, SimpleFloorPlanner, Value},
+        dev::MockProver,
+        halo2curves::pasta::Fp as PastaFp,
+        plonk::{Circuit, ConstraintSystem, Error},
+    };
+    use maingate::{MainGate, MainGateConfig, MainGateInstructions, RegionCtx};
+
+    use super::{num_blocks, sha256, Sha256Chip};
+
+    #[test]
+    fn test_sha256() {
+        assert_eq!(
+            hex::encode(sha256(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(num_blocks(55), 1);
+        assert_eq!(num_blocks(56), 2);
+        assert_eq!(num_blocks(64), 2);
+    }
+
+    #[derive(Clone, Debug, Default)]
+    struct Sha256Circuit<F: FieldExt> {
+        message: Value<Vec<u8>>,
+        max_len: usize,
+        digest: Value<[u8; 32]>,
+        _marker: PhantomData<F>,
+    }
+
+    impl<F: FieldExt> Circuit<F> for Sha256Circuit<F> {
+        type Config = MainGateConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                max_len: self.max_len,
+                ..Default::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            MainGate::<F>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let main_gate = MainGate::<F>::new(config.clone());
+            let sha256_chip = Sha256Chip::new(config);
+
+            layouter.assign_region(
+                || "sha256",
+                |mut region| {
+                    let offset = &mut 0;
+                    let ctx = &mut RegionCtx::new(&mut region, offset);
+
+                    let (message, len) = sha256_chip.assign_message(
+                        ctx,
+                        self.message.as_ref().map(|message| message.as_slice()),
+                        self.max_len,
+                    )?;
+                    let digest = sha256_chip.digest(ctx, &message, &len)?;
+                    let digest = sha256_chip.digest_bytes(ctx, &digest)?;
+                    for (i, byte) in digest.iter().enumerate() {
+                        let expected = self.digest.map(|digest| F::from(digest[i] as u64));
+                        let expected = main_gate.assign_value(ctx, expected)?;
+                        main_gate.assert_equal(ctx, byte, &expected)?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn test_sha256_chip() {
+        // two blocks, the second one only holding the padding of messages of
+        // 56 bytes or more
+        let max_len = 60;
+        let run = |message: &[u8], digest: [u8; 32]| {
+            let circuit = Sha256Circuit::<PastaFp> {
+                message: Value::known(message.to_vec()),
+                max_len,
+                digest: Value::known(digest),
+                _marker: PhantomData,
+            };
+            let prover = MockProver::run(17, &circuit, vec![vec![]]).unwrap();
+            prover.verify()
+        };
+
+        for message in [&b""[..], b"abc", &[0xa5; 55], &[0x5a; 56], &[0xff; 60]] {
+            assert_eq!(run(message, sha256(message)), Ok(()));
+        }
+
+        // wrong digest
+        let mut digest = sha256(b"abc");
+        digest[31] ^= 1;
+        assert!(run(b"abc", digest).is_err());
+        // longer than the capacity
+        assert!(run(&[0; 61], sha256(&[0; 61])).is_err());
+    }
+}
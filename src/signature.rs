@@ -0,0 +1,80 @@
+//! Signature schemes receipts can be signed with.
+//!
+//! `ReceiptCircuit`, as well as the batch and channel circuits, only rely on
+//! this trait to assign the payee's public key and signatures and to verify
+//! the signatures over receipt hashes, so the scheme is chosen at compile time
+//! by their type parameter. See [`crate::ecdsa::Ecdsa`] and
+//! [`crate::schnorr::Schnorr`].
+
+use std::fmt::Debug;
+
+use halo2::{
+    arithmetic::FieldExt,
+    circuit::{Layouter, Value},
+    plonk::{ConstraintSystem, Error},
+};
+use maingate::{AssignedValue, MainGateConfig, RegionCtx};
+
+/// Signature scheme verified in-circuit over the native field `N`.
+///
+/// Values of the implementing type hold the parameters that change the
+/// layout of the circuit but not its constraint system, e.g. the window size
+/// of scalar multiplications.
+pub trait ReceiptSignatureScheme<N: FieldExt>:
+    Clone + Copy + Debug + Default + PartialEq + Eq
+{
+    type PublicKey: Clone + Copy + Debug + Default + PartialEq + Eq;
+    type Signature: Clone + Copy + Debug + Default;
+
+    type Config: Clone + Debug;
+    type Chip;
+    type AssignedPublicKey: Clone + Debug;
+    type AssignedSignature: Clone + Debug;
+
+    /// Configures the columns and gates of the scheme next to `MainGate`.
+    fn configure(meta: &mut ConstraintSystem<N>, main_gate_config: &MainGateConfig)
+        -> Self::Config;
+
+    /// Builds the chip, assigning any values it needs before verification.
+    fn load_chip(
+        &self,
+        config: &Self::Config,
+        layouter: &mut impl Layouter<N>,
+    ) -> Result<Self::Chip, Error>;
+
+    /// Loads lookup tables once every signature is verified.
+    fn load_tables(config: &Self::Config, layouter: &mut impl Layouter<N>) -> Result<(), Error>;
+
+    fn assign_public_key(
+        chip: &Self::Chip,
+        ctx: &mut RegionCtx<'_, N>,
+        public_key: Value<Self::PublicKey>,
+    ) -> Result<Self::AssignedPublicKey, Error>;
+
+    fn assign_signature(
+        chip: &Self::Chip,
+        ctx: &mut RegionCtx<'_, N>,
+        signature: Value<Self::Signature>,
+    ) -> Result<Self::AssignedSignature, Error>;
+
+    /// Constrains `signature` by `public_key` over the receipt hash `msg` to
+    /// be valid.
+    fn verify(
+        chip: &Self::Chip,
+        ctx: &mut RegionCtx<'_, N>,
+        public_key: &Self::AssignedPublicKey,
+        msg: &AssignedValue<N>,
+        signature: &Self::AssignedSignature,
+    ) -> Result<(), Error>;
+
+    /// Native cells encoding `public_key`, which are exposed as public inputs
+    /// and hashed into the registry leaf.
+    fn public_key_cells(public_key: &Self::AssignedPublicKey) -> Vec<AssignedValue<N>>;
+
+    /// Off-circuit values of [`ReceiptSignatureScheme::public_key_cells`].
+    fn public_key_values(public_key: &Self::PublicKey) -> Vec<N>;
+
+    /// Parameters that change the layout of the circuit, see
+    /// `ReceiptCircuit::shape_parameters`.
+    fn shape_parameters(&self) -> Vec<u64>;
+}
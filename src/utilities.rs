@@ -1,5 +1,6 @@
 use ff::PrimeFieldBits;
-mod decompose_running_sum;
+use halo2::{arithmetic::FieldExt, plonk::Expression};
+pub(crate) mod decompose_running_sum;
 
 /// Decompose a word `alpha` into `window_num_bits` bits (little-endian)
 /// For a window size of `w`, this returns [k_0, ..., k_n] where each `k_i`
@@ -23,3 +24,36 @@ pub fn decompose_word<F: PrimeFieldBits>(
         .map(|chunk| chunk.iter().rev().fold(0, |acc, b| (acc << 1) + (*b as u8)))
         .collect()
 }
+
+/// Check that an expression is in the small range [0..range),
+/// i.e. 0 ≤ word < range.
+pub fn range_check<F: FieldExt>(word: Expression<F>, range: usize) -> Expression<F> {
+    (1..range).fold(word.clone(), |acc, i| {
+        acc * (Expression::Constant(F::from(i as u64)) - word.clone())
+    })
+}
+
+/// Rows assigned by a gadget, recorded during synthesis by tests comparing
+/// the cost of gadgets.
+#[cfg(test)]
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RowCount(std::cell::Cell<usize>);
+
+#[cfg(test)]
+impl RowCount {
+    /// Runs `assign` in `ctx`, recording the number of rows it assigns.
+    pub(crate) fn record<'a, F: FieldExt, T>(
+        &self,
+        ctx: &mut maingate::RegionCtx<'a, F>,
+        assign: impl FnOnce(&mut maingate::RegionCtx<'a, F>) -> Result<T, halo2::plonk::Error>,
+    ) -> Result<T, halo2::plonk::Error> {
+        let start = ctx.offset();
+        let assigned = assign(ctx)?;
+        self.0.set(ctx.offset() - start);
+        Ok(assigned)
+    }
+
+    pub(crate) fn get(&self) -> usize {
+        self.0.get()
+    }
+}
@@ -25,13 +25,13 @@
 //! IMPORTANT NOTE: We have copied this from https://github.com/zcash/halo2/tree/main/halo2_gadgets/src/utilities
 
 use ff::PrimeFieldBits;
-use halo2_proofs::{
+use halo2::{
+    arithmetic::FieldExt,
     circuit::{AssignedCell, Region, Value},
     plonk::{Advice, Column, ConstraintSystem, Error, Expression, Selector, VirtualCells},
     poly::Rotation,
 };
 
-use pasta_curves::arithmetic::FieldExt;
 use std::marker::PhantomData;
 
 /// The running sum $[z_0, ..., z_W]$. If created in strict mode, $z_W = 0$.
@@ -0,0 +1,66 @@
+//! WebAuthn assertions, as signed by passkeys with ECDSA over P-256.
+//!
+//! An authenticator signs `authenticatorData || SHA256(clientDataJSON)`, see
+//! [`signed_data`], hashing it once more with SHA-256 for ECDSA. Assertions
+//! are thus verified in-circuit by
+//! [`EcdsaSha256VerifyCircuit`](crate::ecdsa::EcdsaSha256VerifyCircuit) over
+//! the signed data, or by the other ECDSA circuits over [`msg_hash`].
+
+use halo2::arithmetic::CurveAffine;
+
+use crate::{ecdsa::sha256_msg_hash, sha256::sha256};
+
+/// Data signed by an authenticator, i.e. `authenticator_data` followed by
+/// the SHA-256 digest of `client_data_json`.
+pub fn signed_data(authenticator_data: &[u8], client_data_json: &[u8]) -> Vec<u8> {
+    let mut data = authenticator_data.to_vec();
+    data.extend(sha256(client_data_json));
+    data
+}
+
+/// Message hash of ECDSA over curve `C` of an assertion, see [`signed_data`].
+pub fn msg_hash<C: CurveAffine>(authenticator_data: &[u8], client_data_json: &[u8]) -> C::Scalar {
+    sha256_msg_hash::<C>(&signed_data(authenticator_data, client_data_json))
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2::{
+        arithmetic::{CurveAffine, Field},
+        halo2curves::{group::Curve, secp256r1::Secp256r1Affine},
+    };
+    use rand_core::OsRng;
+
+    use super::{msg_hash, signed_data};
+    use crate::{
+        ecdsa::{sign, verify},
+        sha256::sha256,
+    };
+
+    #[test]
+    fn test_webauthn() {
+        // rpIdHash, flags of a user present and verified, signature counter
+        let mut authenticator_data = sha256(b"example.com").to_vec();
+        authenticator_data.extend([0x05, 0, 0, 0, 1]);
+        let client_data_json =
+            br#"{"type":"webauthn.get","challenge":"cmVjZWlwdA","origin":"https://example.com"}"#;
+
+        let data = signed_data(&authenticator_data, client_data_json);
+        assert_eq!(data.len(), authenticator_data.len() + 32);
+        assert_eq!(data[..authenticator_data.len()], authenticator_data[..]);
+        assert_eq!(data[authenticator_data.len()..], sha256(client_data_json));
+
+        let sk = <Secp256r1Affine as CurveAffine>::ScalarExt::random(OsRng);
+        let pk = (Secp256r1Affine::generator() * sk).to_affine();
+        let msg_hash = msg_hash::<Secp256r1Affine>(&authenticator_data, client_data_json);
+        let signature = sign::<Secp256r1Affine>(sk, msg_hash);
+        assert!(verify(pk, msg_hash, signature));
+
+        // assertion of another challenge
+        let other_msg_hash = super::msg_hash::<Secp256r1Affine>(
+            &authenticator_data,
+            br#"{"type":"webauthn.get","challenge":"b3RoZXI","origin":"https://example.com"}"#,
+        );
+        assert!(!verify(pk, other_msg_hash, signature));
+    }
+}